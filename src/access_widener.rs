@@ -0,0 +1,272 @@
+use std::path::Path;
+
+use crate::{
+    fabric, gradle,
+    plan::{self, Plan},
+};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<plan::Error> for Error {
+    fn from(error: plan::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// First line of an access widener, in the mappings used in development
+const HEADER: &str = "accessWidener v2 named";
+
+/// How an access widener entry changes the access to its target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Accessible,
+    Extendable,
+    Mutable,
+}
+
+impl Access {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "accessible" => Some(Access::Accessible),
+            "extendable" => Some(Access::Extendable),
+            "mutable" => Some(Access::Mutable),
+            _ => None,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Access::Accessible => "accessible",
+            Access::Extendable => "extendable",
+            Access::Mutable => "mutable",
+        }
+    }
+}
+
+/// What an access widener entry applies to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Class,
+    Method,
+    Field,
+}
+
+impl Target {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "class" => Some(Target::Class),
+            "method" => Some(Target::Method),
+            "field" => Some(Target::Field),
+            _ => None,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Target::Class => "class",
+            Target::Method => "method",
+            Target::Field => "field",
+        }
+    }
+}
+
+/// Name of the access widener of a mod
+pub fn file_name(mod_id: &str) -> String {
+    format!("{}.accesswidener", mod_id)
+}
+
+/// Adds an access widener to the mod at `path`, declaring it in
+/// fabric.mod.json and in the `loom` block of build.gradle
+pub fn create(plan: &mut Plan, mod_id: &str) -> Result<(), Error> {
+    let name = file_name(mod_id);
+    let resource = format!("src/main/resources/{}", name);
+    let widener_path = plan.path(&resource);
+    if !widener_path.exists() {
+        plan.write(&widener_path, format!("{}\n", HEADER))?;
+    }
+
+    let config_path = plan.path("src/main/resources/fabric.mod.json");
+    let mut config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+    config["accessWidener"] = serde_json::Value::String(name);
+    plan.write_json(&config_path, &config)?;
+
+    let build_path = plan.path("build.gradle");
+    if build_path.exists() {
+        let mut build = std::fs::read_to_string(&build_path)?;
+        if gradle::set_access_widener_path(&mut build, &resource) {
+            plan.write(&build_path, build)?;
+        }
+    }
+    Ok(())
+}
+
+/// Builds an access widener entry. Classes take their name, and methods and
+/// fields their class, name and descriptor. Class names may use dots.
+fn entry(access: Access, target: Target, args: &[String]) -> Result<String, Error> {
+    let (expected, usage) = match target {
+        Target::Class => (1, "<class>"),
+        Target::Method | Target::Field => (3, "<class> <name> <descriptor>"),
+    };
+    if args.len() != expected {
+        return Err(Error {
+            message: format!(
+                "Expected {} {} {}",
+                access.to_string(),
+                target.to_string(),
+                usage
+            ),
+        });
+    }
+    let valid = match access {
+        Access::Accessible => true,
+        Access::Extendable => target != Target::Field,
+        Access::Mutable => target == Target::Field,
+    };
+    if !valid {
+        return Err(Error {
+            message: format!("A {} can't be {}", target.to_string(), access.to_string()),
+        });
+    }
+
+    let class = args[0].replace('.', "/");
+    let mut parts = vec![access.to_string(), target.to_string(), &class];
+    parts.extend(args[1..].iter().map(|arg| arg.as_str()));
+    Ok(parts.join("\t"))
+}
+
+/// Whether a line of an access widener declares `entry`, ignoring whitespace
+/// and comments
+fn declares(line: &str, entry: &str) -> bool {
+    let line = line.split('#').next().unwrap();
+    line.split_whitespace().eq(entry.split_whitespace())
+}
+
+/// Appends an entry to the access widener of the mod at `path`. Returns false
+/// if it's already there.
+pub fn add_entry(
+    path: &Path,
+    access: Access,
+    target: Target,
+    args: &[String],
+) -> Result<bool, Error> {
+    let entry = entry(access, target, args)?;
+
+    let config_path = path.join("src/main/resources/fabric.mod.json");
+    if !config_path.exists() {
+        return Err(Error {
+            message: format!("Not a mod: {}", path.display()),
+        });
+    }
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(config_path)?)?;
+    let widener_path = config["accessWidener"]
+        .as_str()
+        .and_then(|name| fabric::find_resource(path, name))
+        .ok_or_else(|| Error {
+            message: "No access widener found in fabric.mod.json".to_string(),
+        })?;
+
+    let mut contents = std::fs::read_to_string(&widener_path)?;
+    if contents.lines().any(|line| declares(line, &entry)) {
+        return Ok(false);
+    }
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents += &entry;
+    contents.push('\n');
+    std::fs::write(widener_path, contents)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case(Access::Accessible, Target::Class, &["net.minecraft.item.Item"], "accessible\tclass\tnet/minecraft/item/Item")]
+    #[case(Access::Extendable, Target::Method, &["net/minecraft/item/Item", "use", "()V"], "extendable\tmethod\tnet/minecraft/item/Item\tuse\t()V")]
+    #[case(Access::Mutable, Target::Field, &["net/minecraft/item/Item", "maxCount", "I"], "mutable\tfield\tnet/minecraft/item/Item\tmaxCount\tI")]
+    fn test_entry(
+        #[case] access: Access,
+        #[case] target: Target,
+        #[case] input: &[&str],
+        #[case] expected: &str,
+    ) {
+        assert_eq!(entry(access, target, &args(input)).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(Access::Mutable, Target::Class, &["net/minecraft/item/Item"])]
+    #[case(Access::Extendable, Target::Field, &["net/minecraft/item/Item", "maxCount", "I"])]
+    #[case(Access::Accessible, Target::Method, &["net/minecraft/item/Item", "use"])]
+    fn test_entry_invalid(#[case] access: Access, #[case] target: Target, #[case] input: &[&str]) {
+        assert!(entry(access, target, &args(input)).is_err());
+    }
+
+    #[test]
+    fn test_add_entry() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path();
+        let resources = path.join("src/main/resources");
+        fs::create_dir_all(&resources).unwrap();
+        fs::write(resources.join("fabric.mod.json"), "{}").unwrap();
+        fs::write(path.join("build.gradle"), "dependencies {\n}\n").unwrap();
+
+        // Entries can't be added before the access widener exists
+        let class = args(&["net.minecraft.item.Item"]);
+        assert!(add_entry(path, Access::Accessible, Target::Class, &class).is_err());
+
+        create(&mut Plan::new(path), "example-mod").unwrap();
+        let build = fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert!(build.contains(
+            "loom {\n\taccessWidenerPath = file(\"src/main/resources/example-mod.accesswidener\")\n"
+        ));
+        let config = fs::read_to_string(resources.join("fabric.mod.json")).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&config).unwrap();
+        assert_eq!(config["accessWidener"], "example-mod.accesswidener");
+
+        assert!(add_entry(path, Access::Accessible, Target::Class, &class).unwrap());
+        assert!(!add_entry(path, Access::Accessible, Target::Class, &class).unwrap());
+        let contents = fs::read_to_string(resources.join("example-mod.accesswidener")).unwrap();
+        assert_eq!(
+            contents,
+            "accessWidener v2 named\naccessible\tclass\tnet/minecraft/item/Item\n"
+        );
+    }
+}
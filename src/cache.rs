@@ -0,0 +1,451 @@
+use std::path::{Path, PathBuf};
+
+use crate::git;
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    RefNotFound,
+    CloneFailed,
+    NotCached,
+    Other,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+    kind: ErrorKind,
+}
+
+impl Error {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<git::Error> for Error {
+    fn from(error: git::Error) -> Self {
+        let kind = match error.kind() {
+            git::ErrorKind::GitFailed => ErrorKind::CloneFailed,
+            _ => ErrorKind::Other,
+        };
+        Error {
+            message: error.to_string(),
+            kind,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+            kind: ErrorKind::Other,
+        }
+    }
+}
+
+/// A cached clone of a template at a specific ref
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub url: String,
+    pub reference: String,
+    pub path: PathBuf,
+}
+
+/// Local clones of template repositories, keyed by URL and ref
+pub struct Cache {
+    root: PathBuf,
+    offline: bool,
+    /// Where missing entries are cloned to instead of `root`, which is then
+    /// only read from
+    scratch: Option<PathBuf>,
+}
+
+impl Cache {
+    /// Opens the cache in the user's cache directory (e.g.,
+    /// `$XDG_CACHE_HOME/make-fabric-mod`)
+    pub fn new(offline: bool) -> Result<Self, Error> {
+        let root = dirs::cache_dir().ok_or_else(|| Error {
+            message: "Could not determine the cache directory".to_string(),
+            kind: ErrorKind::Other,
+        })?;
+        Ok(Self::at(&root.join("make-fabric-mod"), offline))
+    }
+
+    pub fn at(root: &Path, offline: bool) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            offline,
+            scratch: None,
+        }
+    }
+
+    /// A view of the cache that never writes to it. Cached entries are used
+    /// as they are, without refreshing them, and missing ones are cloned into
+    /// `scratch`.
+    pub fn read_only(&self, scratch: &Path) -> Self {
+        Self {
+            root: self.root.clone(),
+            offline: self.offline,
+            scratch: Some(scratch.to_path_buf()),
+        }
+    }
+
+    fn templates_path(&self) -> PathBuf {
+        self.root.join("templates")
+    }
+
+    fn entry_path(&self, url: &str, reference: &str) -> PathBuf {
+        self.templates_path()
+            .join(escape(url))
+            .join(escape(reference))
+    }
+
+    /// Returns the path of an up-to-date clone of `url` at `reference`,
+    /// cloning or refreshing it first. In offline mode, only existing entries
+    /// are returned.
+    pub fn fetch(&self, url: &str, reference: &str) -> Result<PathBuf, Error> {
+        let path = self.entry_path(url, reference);
+
+        if path.exists() {
+            // Commits never change, so there is nothing to refresh. Entries
+            // cloned from a branch or tag have it as a local ref.
+            let is_commit = git::is_commit_sha(reference)
+                && !git::Context::new(&Some(&path))?.has_ref(reference);
+            if !self.offline && self.scratch.is_none() && !is_commit {
+                // A stale entry is still better than nothing, so only warn if
+                // it can't be refreshed
+                if let Err(error) = refresh(&path, reference) {
+                    eprintln!(
+                        "Could not refresh cached template, using cached copy: {}",
+                        error.to_string().trim()
+                    );
+                }
+            }
+            return Ok(path);
+        }
+
+        if self.offline {
+            return Err(Error {
+                message: format!(
+                    "Template {} ({}) is not cached. Run `make-fabric-mod cache prefetch` while online",
+                    url, reference
+                ),
+                kind: ErrorKind::NotCached,
+            });
+        }
+
+        let path = match &self.scratch {
+            Some(scratch) => {
+                let path = Self::at(scratch, false).entry_path(url, reference);
+                if path.exists() {
+                    return Ok(path);
+                }
+                path
+            }
+            None => path,
+        };
+        // A branch or tag can look like a commit SHA, so ask the remote first
+        let global = git::Context::new(&None)?;
+        let is_commit = git::is_commit_sha(reference) && !global.remote_has_ref(url, reference)?;
+
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let result = if is_commit {
+            std::fs::create_dir(&path)?;
+            let repo = git::Context::new(&Some(&path))?;
+            repo.checkout_commit(url, reference)
+        } else {
+            global
+                .git(&[
+                    "clone",
+                    "--quiet",
+                    "--depth",
+                    "1",
+                    "--branch",
+                    reference,
+                    url,
+                    path.to_str().unwrap(),
+                ])
+                .map(|_| ())
+        };
+
+        if let Err(e) = result {
+            // Don't leave a partial entry behind
+            if path.exists() {
+                std::fs::remove_dir_all(&path)?;
+            }
+
+            // Tell a missing ref apart from an unreachable remote
+            let message = e.to_string();
+            let ref_not_found = matches!(e.kind(), git::ErrorKind::GitFailed)
+                && (message.contains("not found in upstream")
+                    || message.contains("did not match any"));
+            return Err(if ref_not_found {
+                Error {
+                    message,
+                    kind: ErrorKind::RefNotFound,
+                }
+            } else {
+                e.into()
+            });
+        }
+
+        Ok(path)
+    }
+
+    /// Lists the branches of `url`. In offline mode, or if the remote can't
+    /// be reached, only the cached refs of `url` are listed.
+    pub fn branches(&self, url: &str) -> Result<Vec<String>, Error> {
+        if !self.offline {
+            let global = git::Context::new(&None)?;
+            match global.git(&["ls-remote", "--heads", url]) {
+                Ok(output) => {
+                    return Ok(output
+                        .lines()
+                        .filter_map(|line| line.split_once("refs/heads/"))
+                        .map(|(_, branch)| branch.to_string())
+                        .collect())
+                }
+                Err(error) => eprintln!(
+                    "Could not list remote branches, using cached templates: {}",
+                    error.to_string().trim()
+                ),
+            }
+        }
+
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|entry| entry.url == url)
+            .map(|entry| entry.reference)
+            .collect())
+    }
+
+    pub fn list(&self) -> Result<Vec<Entry>, Error> {
+        let mut entries = Vec::new();
+        let templates_path = self.templates_path();
+        if !templates_path.exists() {
+            return Ok(entries);
+        }
+
+        for template in std::fs::read_dir(templates_path)? {
+            let template = template?;
+            for reference in std::fs::read_dir(template.path())? {
+                let reference = reference?;
+                entries.push(Entry {
+                    url: unescape(&template.file_name().to_string_lossy()),
+                    reference: unescape(&reference.file_name().to_string_lossy()),
+                    path: reference.path(),
+                });
+            }
+        }
+
+        entries.sort_by(|a, b| (&a.url, &a.reference).cmp(&(&b.url, &b.reference)));
+        Ok(entries)
+    }
+
+    pub fn clear(&self) -> Result<(), Error> {
+        let templates_path = self.templates_path();
+        if templates_path.exists() {
+            std::fs::remove_dir_all(templates_path)?;
+        }
+        Ok(())
+    }
+}
+
+fn refresh(path: &Path, reference: &str) -> Result<(), git::Error> {
+    let repo = git::Context::new(&Some(path))?;
+    repo.git(&["fetch", "--quiet", "--depth", "1", "origin", reference])?;
+    repo.git(&["reset", "--quiet", "--hard", "FETCH_HEAD"])?;
+    Ok(())
+}
+
+/// Turns a URL or ref into a single, reversible path component
+fn escape(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('/', "%2F")
+        .replace('\\', "%5C")
+        .replace(':', "%3A")
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("%3A", ":")
+        .replace("%5C", "\\")
+        .replace("%2F", "/")
+        .replace("%25", "%")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+    use crate::template::tests::commit_all;
+
+    fn create_repository(path: &Path, branch: &str) -> String {
+        fs::create_dir_all(path).unwrap();
+        fs::write(path.join("gradle.properties"), "a=b").unwrap();
+        commit_all(path, branch);
+        format!("file://{}", path.canonicalize().unwrap().display())
+    }
+
+    #[test]
+    fn test_escape() {
+        let url = "https://github.com/FabricMC/fabric-example-mod%20";
+        assert!(!escape(url).contains('/'));
+        assert_eq!(unescape(&escape(url)), url);
+    }
+
+    #[test]
+    fn test_fetch_clones_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = create_repository(&temp_dir.path().join("template"), "1.19");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+
+        let path = cache.fetch(&url, "1.19").unwrap();
+        assert!(path.join("gradle.properties").exists());
+
+        // The entry is reused offline
+        let offline = Cache::at(&temp_dir.path().join("cache"), true);
+        assert_eq!(offline.fetch(&url, "1.19").unwrap(), path);
+    }
+
+    #[test]
+    fn test_fetch_read_only() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = create_repository(&temp_dir.path().join("template"), "1.19");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+        let read_only = cache.read_only(&temp_dir.path().join("scratch"));
+
+        // Missing entries are cloned outside of the cache
+        let path = read_only.fetch(&url, "1.19").unwrap();
+        assert!(path.starts_with(temp_dir.path().join("scratch")));
+        assert!(path.join("gradle.properties").exists());
+        assert!(cache.list().unwrap().is_empty());
+
+        // Existing entries are used as they are
+        let cached_path = cache.fetch(&url, "1.19").unwrap();
+        assert_eq!(read_only.fetch(&url, "1.19").unwrap(), cached_path);
+    }
+
+    #[test]
+    fn test_fetch_offline_missing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = create_repository(&temp_dir.path().join("template"), "1.19");
+        let cache = Cache::at(&temp_dir.path().join("cache"), true);
+
+        let error = cache.fetch(&url, "1.19").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::NotCached));
+    }
+
+    #[test]
+    fn test_fetch_missing_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = create_repository(&temp_dir.path().join("template"), "1.19");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+
+        let error = cache.fetch(&url, "1.16").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::RefNotFound));
+    }
+
+    #[test]
+    fn test_fetch_unreachable() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = format!("file://{}", temp_dir.path().join("missing").display());
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+
+        let error = cache.fetch(&url, "1.19").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::CloneFailed));
+    }
+
+    #[test]
+    fn test_list_and_clear() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = create_repository(&temp_dir.path().join("template"), "1.19");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+        let path = cache.fetch(&url, "1.19").unwrap();
+
+        assert_eq!(
+            cache.list().unwrap(),
+            vec![Entry {
+                url: url.clone(),
+                reference: "1.19".to_string(),
+                path,
+            }]
+        );
+
+        cache.clear().unwrap();
+        assert!(cache.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fetch_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        let url = create_repository(&template_path, "1.19");
+        let output = std::process::Command::new("git")
+            .current_dir(&template_path)
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .unwrap();
+        let commit = String::from_utf8(output.stdout).unwrap().trim().to_string();
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+
+        let path = cache.fetch(&url, &commit).unwrap();
+        assert!(path.join("gradle.properties").exists());
+
+        let error = cache.fetch(&url, "0000000").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::RefNotFound));
+        assert!(!cache.entry_path(&url, "0000000").exists());
+    }
+
+    #[test]
+    fn test_fetch_branch_like_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        let url = create_repository(&template_path, "cafe123");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+
+        // The branch is cloned, and refreshed like any other
+        let path = cache.fetch(&url, "cafe123").unwrap();
+        assert_eq!(
+            fs::read_to_string(path.join("gradle.properties")).unwrap(),
+            "a=b"
+        );
+        fs::write(template_path.join("gradle.properties"), "a=c").unwrap();
+        commit_all(&template_path, "cafe123");
+        let path = cache.fetch(&url, "cafe123").unwrap();
+        assert_eq!(
+            fs::read_to_string(path.join("gradle.properties")).unwrap(),
+            "a=c"
+        );
+    }
+
+    #[test]
+    fn test_branches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        let url = create_repository(&template_path, "1.19");
+        fs::write(template_path.join("gradle.properties"), "a=c").unwrap();
+        commit_all(&template_path, "1.20");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+
+        let mut branches = cache.branches(&url).unwrap();
+        branches.sort();
+        assert_eq!(branches, vec!["1.19", "1.20"]);
+
+        // Offline, only cached refs are known
+        let offline = Cache::at(&temp_dir.path().join("cache"), true);
+        assert!(offline.branches(&url).unwrap().is_empty());
+        cache.fetch(&url, "1.19").unwrap();
+        assert_eq!(offline.branches(&url).unwrap(), vec!["1.19"]);
+    }
+}
@@ -1,8 +1,18 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{builder::PossibleValuesParser, Args, Parser, Subcommand};
 
-use crate::{code::language::Language, fabric};
+use crate::{
+    access_widener::{self, Access, Target},
+    cache::{self, Cache},
+    code::{entrypoint::EntrypointKind, language::Language},
+    fabric::{self, Environment},
+    minecraft::{self, MinecraftVersion},
+    mixin, plan, port,
+    rename::{self, Renames},
+    template::{self, Template},
+    update,
+};
 
 #[derive(Debug)]
 pub struct Error {
@@ -23,13 +33,106 @@ impl From<fabric::Error> for Error {
     }
 }
 
+impl From<template::Error> for Error {
+    fn from(error: template::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<port::Error> for Error {
+    fn from(error: port::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<update::Error> for Error {
+    fn from(error: update::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<minecraft::Error> for Error {
+    fn from(error: minecraft::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<mixin::Error> for Error {
+    fn from(error: mixin::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<access_widener::Error> for Error {
+    fn from(error: access_widener::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<plan::Error> for Error {
+    fn from(error: plan::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<rename::Error> for Error {
+    fn from(error: rename::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<cache::Error> for Error {
+    fn from(error: cache::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(
     author = "Caleb Sacks",
     version = "0.1.1",
-    about = "Create a new Fabric mod"
+    about = "Create a new Fabric mod",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Opts {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    create: CreateOpts,
+}
+
+// The required arguments are optional here so that they can be omitted when
+// a subcommand is used. Clap still enforces them otherwise.
+#[derive(Args, Debug)]
+struct CreateOpts {
     #[clap(
         short = 'i',
         long = "id",
@@ -38,38 +141,642 @@ struct Opts {
     )]
     mod_id: String,
 
-    #[clap(short = 'n', long = "name", help = "Human-friendly mod name")]
-    name: String,
+    #[clap(
+        short = 'n',
+        long = "name",
+        help = "Human-friendly mod name",
+        required = true
+    )]
+    name: Option<String>,
 
-    #[clap(short = 'm', long = "minecraft", help = "Minecraft version (x.y)")]
-    minecraft_version: String,
+    #[clap(
+        short = 'm',
+        long = "minecraft",
+        help = "Minecraft version (e.g., 1.19.4 or 23w13a)",
+        required = true
+    )]
+    minecraft_version: Option<String>,
 
     #[clap(short = 'k', long = "kotlin", help = "Use Kotlin instead of Java")]
     kotlin: bool,
 
+    #[clap(
+        short = 'l',
+        long = "lang",
+        help = "Source language",
+        value_parser = languages(),
+        conflicts_with = "kotlin"
+    )]
+    lang: Option<String>,
+
+    #[clap(
+        long = "kotlin-entrypoint",
+        value_name = "KIND",
+        help = "Kotlin main class: class, object or function",
+        value_parser = ["class", "object", "function"],
+        hide_possible_values = true
+    )]
+    kotlin_entrypoint: Option<String>,
+
     #[clap(
         short = 'e',
         long = "entrypoint",
-        help = "Main class (e.g., 'net.fabricmc.example.ExampleMod')"
+        help = "Main class (e.g., 'net.fabricmc.example.ExampleMod')",
+        required = true
+    )]
+    main_class: Option<String>,
+
+    #[clap(
+        long = "client-entrypoint",
+        value_name = "CLASS",
+        help = "Client entrypoint class"
     )]
-    main_class: String,
+    client_class: Option<String>,
+
+    #[clap(long = "split-sources", help = "Split client and common source sets")]
+    split_sources: bool,
 
-    path: PathBuf,
+    #[clap(
+        long = "environment",
+        value_name = "ENV",
+        help = "Where the mod runs: client, server or both",
+        value_parser = ["client", "server", "both"],
+        hide_possible_values = true
+    )]
+    environment: Option<String>,
+
+    #[clap(long = "datagen", help = "Add a data generation entrypoint")]
+    datagen: bool,
+
+    #[clap(long = "access-widener", help = "Add an access widener")]
+    access_widener: bool,
+
+    #[clap(
+        short = 't',
+        long = "template",
+        help = "Template directory, git URL or builtin:<lang>"
+    )]
+    template: Option<String>,
+
+    #[clap(long = "template-ref", help = "Template branch, tag or commit")]
+    template_ref: Option<String>,
+
+    #[clap(long = "offline", help = "Only use cached templates")]
+    offline: bool,
+
+    #[clap(
+        long = "meta",
+        help = "Dependency versions (Fabric meta JSON file or URL)"
+    )]
+    meta: Option<String>,
+
+    #[clap(
+        long = "dry-run",
+        help = "Print the planned changes without making them"
+    )]
+    dry_run: bool,
+
+    #[clap(
+        long = "keep-failed",
+        help = "Keep the staging directory if generation fails"
+    )]
+    keep_failed: bool,
+
+    #[clap(required = true)]
+    path: Option<PathBuf>,
 }
 
-pub fn cli() -> Result<(), Error> {
-    let opts = Opts::parse();
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage the local template cache
+    #[command(subcommand)]
+    Cache(CacheCommand),
+
+    /// List the Minecraft versions supported by the template
+    Versions {
+        #[clap(short = 'k', long = "kotlin", help = "Use Kotlin instead of Java")]
+        kotlin: bool,
+
+        #[clap(
+            short = 'l',
+            long = "lang",
+            help = "Source language",
+            value_parser = languages(),
+            conflicts_with = "kotlin"
+        )]
+        lang: Option<String>,
+
+        #[clap(
+            short = 't',
+            long = "template",
+            help = "Template git repository or git URL"
+        )]
+        template: Option<String>,
+
+        #[clap(long = "json", help = "Print the versions as a JSON array")]
+        json: bool,
+
+        #[clap(long = "offline", help = "Only use cached templates")]
+        offline: bool,
+    },
+
+    /// Merge changes from the template into an existing mod
+    Update {
+        #[clap(
+            long = "template-ref",
+            help = "Template branch, tag or commit to update to"
+        )]
+        template_ref: Option<String>,
+
+        #[clap(long = "offline", help = "Only use cached templates")]
+        offline: bool,
+
+        #[clap(default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// Add code to an existing mod
+    #[command(subcommand)]
+    Add(AddCommand),
+
+    /// Manage the access widener of an existing mod
+    #[command(subcommand)]
+    Aw(AwCommand),
+
+    /// Rename the ID, name, package or main class of an existing mod
+    Rename {
+        #[clap(short = 'i', long = "id", help = "New mod ID")]
+        mod_id: Option<String>,
+
+        #[clap(short = 'n', long = "name", help = "New mod name")]
+        name: Option<String>,
+
+        #[clap(short = 'p', long = "package", help = "New package")]
+        package: Option<String>,
+
+        #[clap(
+            short = 'm',
+            long = "main-class",
+            help = "New main class (simple or fully qualified)"
+        )]
+        main_class: Option<String>,
+
+        #[clap(long = "force", help = "Rename even with uncommitted changes")]
+        force: bool,
+
+        #[clap(default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// Move an existing mod to another Minecraft version
+    Port {
+        #[clap(
+            short = 'm',
+            long = "minecraft",
+            help = "Minecraft version to port to (e.g., 1.20.1)"
+        )]
+        minecraft_version: String,
+
+        #[clap(long = "offline", help = "Only use cached templates")]
+        offline: bool,
+
+        #[clap(default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AddCommand {
+    /// Generate a mixin and register it in the mixin config
+    Mixin {
+        #[clap(help = "Class to mix into (e.g., net.minecraft.client.MinecraftClient)")]
+        target: String,
+
+        #[clap(
+            long = "client",
+            help = "Only apply on the client",
+            conflicts_with = "server"
+        )]
+        client: bool,
+
+        #[clap(long = "server", help = "Only apply on the dedicated server")]
+        server: bool,
+
+        #[clap(long = "name", help = "Mixin class name. Defaults to <Target>Mixin")]
+        name: Option<String>,
+
+        #[clap(
+            long = "inject",
+            value_name = "METHOD",
+            help = "Inject at the start of a method (e.g., 'init()V')"
+        )]
+        inject: Option<String>,
+
+        #[clap(default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// Generate an accessor mixin exposing private fields and methods
+    Accessor {
+        #[clap(help = "Class to access (e.g., net.minecraft.entity.Entity)")]
+        target: String,
+
+        #[clap(
+            long = "field",
+            value_name = "NAME:TYPE",
+            help = "Field to get and set"
+        )]
+        fields: Vec<String>,
+
+        #[clap(
+            long = "method",
+            value_name = "NAME(ARGS)RET",
+            help = "Method to invoke (e.g., 'damage(float)boolean')"
+        )]
+        methods: Vec<String>,
+
+        #[clap(long = "name", help = "Interface name. Defaults to <Target>Accessor")]
+        name: Option<String>,
+
+        #[clap(default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AwCommand {
+    /// Add an entry to the access widener
+    Add {
+        #[clap(value_parser = ["accessible", "extendable", "mutable"])]
+        access: String,
+
+        #[clap(value_parser = ["class", "method", "field"])]
+        target: String,
+
+        #[clap(
+            required = true,
+            help = "Class, or class, name and descriptor of a method or field"
+        )]
+        args: Vec<String>,
+
+        #[clap(long = "path", help = "Mod directory", default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// List cached templates
+    List,
+
+    /// Remove all cached templates
+    Clear,
+
+    /// Download templates for the given Minecraft versions
+    Prefetch {
+        #[clap(required = true, help = "Minecraft versions (e.g., 1.19.4)")]
+        versions: Vec<String>,
+
+        #[clap(short = 'k', long = "kotlin", help = "Use Kotlin instead of Java")]
+        kotlin: bool,
+
+        #[clap(
+            short = 'l',
+            long = "lang",
+            help = "Source language",
+            value_parser = languages(),
+            conflicts_with = "kotlin"
+        )]
+        lang: Option<String>,
+
+        #[clap(
+            short = 't',
+            long = "template",
+            help = "Template git repository or git URL"
+        )]
+        template: Option<String>,
+    },
+}
+
+/// Values accepted by `--lang`
+fn languages() -> PossibleValuesParser {
+    PossibleValuesParser::new(Language::all().map(|language| language.to_string()))
+}
+
+fn language(kotlin: bool, lang: Option<&str>) -> Language {
+    match lang {
+        Some(name) => Language::from_name(name).unwrap(),
+        None if kotlin => Language::KOTLIN,
+        None => Language::JAVA,
+    }
+}
+
+fn template(template: Option<String>, language: &Language) -> Result<Template, Error> {
+    match template {
+        Some(template) => Ok(Template::parse(&template)?),
+        None => Ok(Template::official(language)),
+    }
+}
+
+fn create(opts: CreateOpts) -> Result<(), Error> {
+    let path = opts.path.unwrap();
     let mod_id = if opts.mod_id.is_empty() {
-        opts.path.file_name().unwrap().to_str().unwrap().to_string()
+        path.file_name().unwrap().to_str().unwrap().to_string()
     } else {
         opts.mod_id
     };
-    let language = if opts.kotlin {
-        Language::Kotlin
-    } else {
-        Language::Java
+    let language = language(opts.kotlin, opts.lang.as_deref());
+    if opts.kotlin_entrypoint.is_some() && language != Language::KOTLIN {
+        return Err(Error {
+            message: "--kotlin-entrypoint requires --lang kotlin".to_string(),
+        });
+    }
+    let options = fabric::Options {
+        mod_id,
+        name: opts.name.unwrap(),
+        main_class: opts.main_class.unwrap(),
+        language,
+        minecraft_version: opts.minecraft_version.unwrap().parse()?,
+        template: template(opts.template, &language)?,
+        template_ref: opts.template_ref,
+        meta: opts.meta,
+        entrypoint_kind: opts
+            .kotlin_entrypoint
+            .as_deref()
+            .and_then(EntrypointKind::from_name),
+        client_class: opts.client_class,
+        split_sources: opts.split_sources,
+        environment: opts
+            .environment
+            .as_deref()
+            .and_then(Environment::from_name)
+            .unwrap_or(Environment::Both),
+        datagen: opts.datagen,
+        access_widener: opts.access_widener,
     };
 
-    fabric::create_mod(&opts.path, &mod_id, &opts.minecraft_version, &language, &opts.main_class, &opts.name)?;
+    // The built-in templates only exist for one version (and are the default
+    // for Scala), so reject other versions before doing anything
+    if let Template::Builtin(language) = &options.template {
+        if options.minecraft_version.template_branch() != template::builtin::MINECRAFT_VERSION {
+            return Err(Error {
+                message: format!(
+                    "The built-in {} template only supports Minecraft {}. Use --template to generate the mod from another template",
+                    language.to_string(),
+                    template::builtin::MINECRAFT_VERSION
+                ),
+            });
+        }
+    }
+
+    let cache = Cache::new(opts.offline)?;
+    if opts.dry_run {
+        // Templates that aren't cached yet are cloned next to the plan, so
+        // nothing outlives the dry run
+        let work = tempfile::tempdir()?;
+        let cache = cache.read_only(&work.path().join("cache"));
+        let plan = fabric::plan_mod(work.path(), &options, &cache)?;
+        println!("Plan for {}:", path.display());
+        for (operation, files) in plan.operation_files() {
+            println!("  {}", operation);
+            for file in files {
+                println!("    {}", file.display());
+            }
+        }
+        return Ok(());
+    }
+    fabric::generate_mod(&path, &options, &cache, opts.keep_failed)?;
+    Ok(())
+}
+
+fn cache(command: CacheCommand) -> Result<(), Error> {
+    let cache = Cache::new(false)?;
+    match command {
+        CacheCommand::List => {
+            for entry in cache.list()? {
+                println!("{} {}", entry.url, entry.reference);
+            }
+        }
+        CacheCommand::Clear => cache.clear()?,
+        CacheCommand::Prefetch {
+            versions,
+            kotlin,
+            lang,
+            template: source,
+        } => {
+            let template = template(source, &language(kotlin, lang.as_deref()))?;
+            let url = template.cache_url().ok_or_else(|| Error {
+                message: format!("Only git templates can be cached: {}", template),
+            })?;
+            for version in versions {
+                let branch = version.parse::<MinecraftVersion>()?.template_branch();
+                println!("Fetching {} ({})...", url, branch);
+                cache.fetch(&url, &branch)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn versions(
+    kotlin: bool,
+    lang: Option<String>,
+    template_source: Option<String>,
+    json: bool,
+    offline: bool,
+) -> Result<(), Error> {
+    let template = template(template_source, &language(kotlin, lang.as_deref()))?;
+    let versions = template
+        .versions(&Cache::new(offline)?)?
+        .iter()
+        .map(|version| version.to_string())
+        .collect::<Vec<_>>();
+    if json {
+        println!("{}", serde_json::Value::from(versions));
+    } else {
+        for version in versions {
+            println!("{}", version);
+        }
+    }
     Ok(())
 }
+
+fn update(path: PathBuf, template_ref: Option<String>, offline: bool) -> Result<(), Error> {
+    let cache = Cache::new(offline)?;
+    let changes = update::update_mod(&path, template_ref.as_deref(), &cache)?;
+    if changes.is_empty() {
+        println!("Already up to date");
+    }
+    for (file, change) in &changes {
+        println!("{:>8} {}", change.to_string(), file.display());
+    }
+    if changes
+        .iter()
+        .any(|(_, change)| *change == update::Change::Conflict)
+    {
+        println!("Resolve the conflicts and commit the result");
+    }
+    Ok(())
+}
+
+fn port(path: PathBuf, minecraft_version: String, offline: bool) -> Result<(), Error> {
+    let minecraft_version = minecraft_version.parse::<MinecraftVersion>()?;
+    let cache = Cache::new(offline)?;
+    let report = port::port_mod(&path, &minecraft_version, &cache)?;
+    for (file, change) in &report.changes {
+        println!("{:>8} {}", change.to_string(), file.display());
+    }
+
+    let unresolved = report
+        .changes
+        .iter()
+        .filter(|(_, change)| matches!(change, update::Change::Conflict | update::Change::Skipped))
+        .map(|(file, _)| file)
+        .chain(&report.not_applied)
+        .collect::<Vec<_>>();
+    if unresolved.is_empty() {
+        println!("Ported to Minecraft {}", minecraft_version);
+    } else {
+        println!(
+            "Ported to Minecraft {}. Review these files, which the template changed:",
+            minecraft_version
+        );
+        for file in unresolved {
+            println!("  {}", file.display());
+        }
+    }
+    Ok(())
+}
+
+fn add(command: AddCommand) -> Result<(), Error> {
+    match command {
+        AddCommand::Mixin {
+            target,
+            client,
+            server,
+            name,
+            inject,
+            path,
+        } => {
+            let environment = match (client, server) {
+                (true, _) => Environment::Client,
+                (_, true) => Environment::Server,
+                _ => Environment::Both,
+            };
+            let class_path = mixin::add_mixin(
+                &path,
+                &target,
+                environment,
+                name.as_deref(),
+                inject.as_deref(),
+            )?;
+            println!("Created {}", class_path.display());
+        }
+        AddCommand::Accessor {
+            target,
+            fields,
+            methods,
+            name,
+            path,
+        } => {
+            let class_path =
+                mixin::add_accessor(&path, &target, &fields, &methods, name.as_deref())?;
+            println!("Created {}", class_path.display());
+        }
+    }
+    Ok(())
+}
+
+fn aw(command: AwCommand) -> Result<(), Error> {
+    match command {
+        AwCommand::Add {
+            access,
+            target,
+            args,
+            path,
+        } => {
+            let access = Access::from_name(&access).unwrap();
+            let target = Target::from_name(&target).unwrap();
+            if access_widener::add_entry(&path, access, target, &args)? {
+                println!(
+                    "Added {} {} {}",
+                    access.to_string(),
+                    target.to_string(),
+                    args.join(" ")
+                );
+            } else {
+                println!("Already declared");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn rename(path: PathBuf, renames: Renames, force: bool) -> Result<(), Error> {
+    let (old, new) = rename::rename_mod(&path, &renames, force)?;
+    let property = |value: &Option<String>| value.clone().unwrap_or_default();
+    for (label, old, new) in [
+        ("ID", old.mod_id.clone(), new.mod_id.clone()),
+        ("Name", old.name.clone(), new.name.clone()),
+        ("Main class", old.main_class.clone(), new.main_class.clone()),
+        (
+            "Maven group",
+            property(&old.maven_group),
+            property(&new.maven_group),
+        ),
+        (
+            "Archives base name",
+            property(&old.archives_base_name),
+            property(&new.archives_base_name),
+        ),
+    ] {
+        if old != new {
+            println!("{}: {} -> {}", label, old, new);
+        }
+    }
+    Ok(())
+}
+
+pub fn cli() -> Result<(), Error> {
+    let opts = Opts::parse();
+    match opts.command {
+        Some(Command::Cache(command)) => cache(command),
+        Some(Command::Versions {
+            kotlin,
+            lang,
+            template,
+            json,
+            offline,
+        }) => versions(kotlin, lang, template, json, offline),
+        Some(Command::Update {
+            template_ref,
+            offline,
+            path,
+        }) => update(path, template_ref, offline),
+        Some(Command::Add(command)) => add(command),
+        Some(Command::Aw(command)) => aw(command),
+        Some(Command::Rename {
+            mod_id,
+            name,
+            package,
+            main_class,
+            force,
+            path,
+        }) => rename(
+            path,
+            Renames {
+                mod_id,
+                name,
+                package,
+                main_class,
+            },
+            force,
+        ),
+        Some(Command::Port {
+            minecraft_version,
+            offline,
+            path,
+        }) => port(path, minecraft_version, offline),
+        None => create(opts.create),
+    }
+}
@@ -1,2 +1,4 @@
+pub mod entrypoint;
 pub mod language;
+pub mod lexer;
 pub mod refactor;
@@ -0,0 +1,222 @@
+use super::{lexer::is_identifier_char, refactor::rename_identifier};
+
+/// How the main entrypoint is declared in the source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntrypointKind {
+    /// A class implementing `ModInitializer`, instantiated by Fabric Loader
+    Class,
+    /// A singleton object, loaded through a language adapter
+    Object,
+    /// A top-level function, loaded through a language adapter
+    Function,
+}
+
+/// Rewrites the main class (source, simple class name) into another kind of
+/// entrypoint
+pub type Reshape = fn(&str, &str, EntrypointKind) -> Option<String>;
+
+/// Name of the top-level function generated for `EntrypointKind::Function`
+pub const FUNCTION_NAME: &str = "onInitialize";
+
+impl EntrypointKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "class" => Some(EntrypointKind::Class),
+            "object" => Some(EntrypointKind::Object),
+            "function" => Some(EntrypointKind::Function),
+            _ => None,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            EntrypointKind::Class => "class",
+            EntrypointKind::Object => "object",
+            EntrypointKind::Function => "function",
+        }
+    }
+
+    /// The value of the entrypoint in fabric.mod.json. Top-level functions
+    /// are referenced through the class Kotlin generates for the file (e.g.,
+    /// `ExampleModKt::onInitialize`).
+    pub fn value(self, main_class: &str) -> String {
+        match self {
+            EntrypointKind::Class | EntrypointKind::Object => main_class.to_string(),
+            EntrypointKind::Function => format!("{}Kt::{}", main_class, FUNCTION_NAME),
+        }
+    }
+}
+
+/// Turns a main initializer into a client initializer, for mods that only run
+/// on the client. The source is the same in each language apart from the
+/// keywords.
+pub fn to_client_initializer(source: &str) -> String {
+    let source = rename_identifier(source, "ModInitializer", "ClientModInitializer");
+    rename_identifier(&source, "onInitialize", "onInitializeClient")
+}
+
+/// Whether `line` declares the class `name`
+fn declares_class(line: &str, name: &str) -> bool {
+    line.trim_start()
+        .strip_prefix("class ")
+        .and_then(|rest| rest.trim_start().strip_prefix(name))
+        .is_some_and(|rest| !rest.starts_with(is_identifier_char))
+}
+
+/// Returns the index of the line closing the block opened on line `start`
+fn closing_line(lines: &[&str], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let mut in_string = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if in_string => {
+                    chars.next();
+                }
+                '"' => in_string = !in_string,
+                '/' if !in_string && chars.peek() == Some(&'/') => break,
+                '{' if !in_string => depth += 1,
+                '}' if !in_string => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(index);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// Removes one level of indentation
+fn dedent(line: &str) -> &str {
+    line.strip_prefix('\t')
+        .or_else(|| line.strip_prefix("    "))
+        .unwrap_or(line)
+}
+
+/// Rewrites the Kotlin class `class_name` (a simple name) in `source` into
+/// another kind of entrypoint. Returns `None` if the class isn't declared as
+/// expected.
+pub fn reshape_kotlin(source: &str, class_name: &str, kind: EntrypointKind) -> Option<String> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let start = lines
+        .iter()
+        .position(|line| declares_class(line, class_name))?;
+
+    let mut reshaped = match kind {
+        EntrypointKind::Class => return Some(source.to_string()),
+        EntrypointKind::Object => {
+            let mut lines = lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>();
+            lines[start] = lines[start].replacen("class ", "object ", 1);
+            lines
+        }
+        EntrypointKind::Function => {
+            // Move the members of the class to the top level, turning the
+            // initializer into a plain function
+            let end = closing_line(&lines, start)?;
+            let initializer = format!("override fun {}(", FUNCTION_NAME);
+            let function = format!("fun {}(", FUNCTION_NAME);
+            lines[..start]
+                .iter()
+                .filter(|line| line.trim() != "import net.fabricmc.api.ModInitializer")
+                .map(|line| line.to_string())
+                .chain(
+                    lines[start + 1..end]
+                        .iter()
+                        .map(|line| dedent(line).replace(&initializer, &function)),
+                )
+                .chain(lines[end + 1..].iter().map(|line| line.to_string()))
+                .collect()
+        }
+    }
+    .join("\n");
+    if source.ends_with('\n') {
+        reshaped.push('\n');
+    }
+    Some(reshaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "package net.fabricmc.example
+
+import net.fabricmc.api.ModInitializer
+import org.slf4j.LoggerFactory
+
+class ExampleMod : ModInitializer {
+    private val logger = LoggerFactory.getLogger(\"modid\")
+
+    override fun onInitialize() {
+        logger.info(\"Hello {world}!\") // }
+    }
+}
+";
+
+    #[test]
+    fn test_value() {
+        let main_class = "net.fabricmc.example.ExampleMod";
+        assert_eq!(EntrypointKind::Object.value(main_class), main_class);
+        assert_eq!(
+            EntrypointKind::Function.value(main_class),
+            "net.fabricmc.example.ExampleModKt::onInitialize"
+        );
+    }
+
+    #[test]
+    fn test_to_client_initializer() {
+        assert_eq!(
+            to_client_initializer(SOURCE),
+            SOURCE
+                .replace("api.ModInitializer", "api.ClientModInitializer")
+                .replace(": ModInitializer", ": ClientModInitializer")
+                .replace("fun onInitialize()", "fun onInitializeClient()")
+        );
+        assert_eq!(
+            to_client_initializer("ClientModInitializer onInitializeServer"),
+            "ClientModInitializer onInitializeServer"
+        );
+    }
+
+    #[test]
+    fn test_reshape_kotlin_object() {
+        let reshaped = reshape_kotlin(SOURCE, "ExampleMod", EntrypointKind::Object).unwrap();
+        assert_eq!(
+            reshaped,
+            SOURCE.replace("class ExampleMod :", "object ExampleMod :")
+        );
+    }
+
+    #[test]
+    fn test_reshape_kotlin_function() {
+        let reshaped = reshape_kotlin(SOURCE, "ExampleMod", EntrypointKind::Function).unwrap();
+        assert_eq!(
+            reshaped,
+            "package net.fabricmc.example
+
+import org.slf4j.LoggerFactory
+
+private val logger = LoggerFactory.getLogger(\"modid\")
+
+fun onInitialize() {
+    logger.info(\"Hello {world}!\") // }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_reshape_kotlin_missing_class() {
+        assert_eq!(
+            reshape_kotlin(SOURCE, "ExampleMod2", EntrypointKind::Object),
+            None
+        );
+    }
+}
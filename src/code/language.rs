@@ -1,20 +1,172 @@
-pub enum Language {
-    Java,
-    Kotlin,
+use crate::template::builtin::{self, Files};
+
+use super::entrypoint::{self, EntrypointKind, Reshape};
+
+/// Describes how mods are written in a JVM language. Adding a language only
+/// takes a new backend (and its built-in template) registered in `BACKENDS`.
+pub struct Backend {
+    /// Name used by `--lang`, `builtin:<lang>` and the lockfile
+    pub name: &'static str,
+    /// Extension of the source files
+    pub extension: &'static str,
+    /// Directory of the module under `src/main`
+    pub source_dir: &'static str,
+    /// Language adapter loading entrypoints that aren't classes
+    pub adapter: Option<&'static str>,
+    /// ID of the mod providing the language adapter and runtime
+    pub library: Option<&'static str>,
+    /// How the main entrypoint is declared in the templates
+    pub entrypoint: EntrypointKind,
+    /// Rewrites the main class of the templates into another kind of
+    /// entrypoint, if the language supports others
+    pub reshape: Option<Reshape>,
+    /// ID of the Gradle plugin compiling the sources, if Loom doesn't
+    pub plugin: Option<&'static str>,
+    /// Git URL of the official template, if there is one. The built-in
+    /// template is used otherwise.
+    pub template: Option<&'static str>,
+    /// Files of the built-in template
+    pub builtin: Files,
+    /// Source of a client entrypoint, declaring `CLIENT_CLASS`
+    pub client: &'static str,
+    /// Source of a data generation entrypoint, declaring `DATAGEN_CLASS`
+    pub datagen: &'static str,
+    /// Language of the module containing the mixins, if it's not this one
+    pub mixin_language: Option<&'static str>,
 }
 
+/// Class declared by the client entrypoint sources
+pub const CLIENT_CLASS: &str = "net.fabricmc.example.ExampleModClient";
+
+/// Class declared by the data generation entrypoint sources
+pub const DATAGEN_CLASS: &str = "net.fabricmc.example.ExampleModDataGenerator";
+
+const JAVA: Backend = Backend {
+    name: "java",
+    extension: "java",
+    source_dir: "java",
+    adapter: None,
+    library: None,
+    entrypoint: EntrypointKind::Class,
+    reshape: None,
+    plugin: None,
+    template: Some("https://github.com/FabricMC/fabric-example-mod"),
+    builtin: builtin::JAVA,
+    client: include_str!("../../templates/client/ExampleModClient.java"),
+    datagen: include_str!("../../templates/datagen/ExampleModDataGenerator.java"),
+    mixin_language: None,
+};
+
+const KOTLIN: Backend = Backend {
+    name: "kotlin",
+    extension: "kt",
+    source_dir: "kotlin",
+    adapter: Some("kotlin"),
+    library: Some("fabric-language-kotlin"),
+    entrypoint: EntrypointKind::Class,
+    reshape: Some(entrypoint::reshape_kotlin),
+    plugin: Some("org.jetbrains.kotlin.jvm"),
+    template: Some("https://github.com/clabe45/fabric-example-mod-kotlin"),
+    builtin: builtin::KOTLIN,
+    client: include_str!("../../templates/client/ExampleModClient.kt"),
+    datagen: include_str!("../../templates/datagen/ExampleModDataGenerator.kt"),
+    mixin_language: Some("java"),
+};
+
+const SCALA: Backend = Backend {
+    name: "scala",
+    extension: "scala",
+    source_dir: "scala",
+    adapter: Some("scala"),
+    library: Some("fabric-language-scala"),
+    entrypoint: EntrypointKind::Object,
+    reshape: None,
+    plugin: Some("scala"),
+    // There is no example mod for Scala
+    template: None,
+    builtin: builtin::SCALA,
+    client: include_str!("../../templates/client/ExampleModClient.scala"),
+    datagen: include_str!("../../templates/datagen/ExampleModDataGenerator.scala"),
+    mixin_language: Some("java"),
+};
+
+/// The supported languages
+const BACKENDS: &[Language] = &[Language::JAVA, Language::KOTLIN, Language::SCALA];
+
+/// A language mods can be written in
+#[derive(Clone, Copy)]
+pub struct Language(&'static Backend);
+
 impl Language {
-    pub fn extension(&self) -> &str {
-        match self {
-            Language::Java => "java",
-            Language::Kotlin => "kt",
-        }
+    pub const JAVA: Self = Self(&JAVA);
+    pub const KOTLIN: Self = Self(&KOTLIN);
+    pub const SCALA: Self = Self(&SCALA);
+
+    /// Lists the supported languages
+    pub fn all() -> impl Iterator<Item = Self> {
+        BACKENDS.iter().copied()
+    }
+
+    pub fn backend(&self) -> &'static Backend {
+        self.0
+    }
+
+    pub fn extension(&self) -> &'static str {
+        self.0.extension
+    }
+
+    /// Directory of the module under `src/main`
+    pub fn source_dir(&self) -> &'static str {
+        self.0.source_dir
     }
 
-    pub fn to_string(&self) -> &str {
-        match self {
-            Language::Java => "java",
-            Language::Kotlin => "kotlin",
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().find(|language| language.0.name == name)
+    }
+
+    pub fn to_string(self) -> &'static str {
+        self.0.name
+    }
+
+    /// The modules to refactor, starting with the one containing the main
+    /// class
+    pub fn modules(&self) -> Vec<Language> {
+        let mut modules = vec![*self];
+        modules.extend(self.0.mixin_language.and_then(Self::from_name));
+        modules
+    }
+}
+
+impl PartialEq for Language {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.name == other.0.name
+    }
+}
+
+impl std::fmt::Debug for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        for language in Language::all() {
+            assert_eq!(Language::from_name(language.to_string()), Some(language));
         }
+        assert_eq!(Language::from_name("cobol"), None);
+    }
+
+    #[test]
+    fn test_modules() {
+        assert_eq!(Language::JAVA.modules(), vec![Language::JAVA]);
+        assert_eq!(
+            Language::SCALA.modules(),
+            vec![Language::SCALA, Language::JAVA]
+        );
     }
 }
@@ -0,0 +1,262 @@
+/// Kind of a token of Java, Kotlin or Scala source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    /// Names and keywords
+    Identifier,
+    Number,
+    /// String, text block, raw string or character literal
+    String,
+    Comment,
+    Whitespace,
+    /// Any other character (e.g., `.`, `;` or `{`)
+    Symbol,
+}
+
+/// A slice of the source. The tokens of a source add up to the whole source,
+/// so it can be rewritten token by token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+pub fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Words that can't be used as names in Java
+const JAVA_RESERVED: &[&str] = &[
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+    "_",
+];
+
+/// Whether `name` can name a class, a method or a field in Java
+pub fn is_java_identifier(name: &str) -> bool {
+    name.starts_with(is_identifier_start)
+        && name.chars().all(is_identifier_char)
+        && !JAVA_RESERVED.contains(&name)
+}
+
+/// Returns the length of the literal quoted by `quote` at the start of
+/// `source`. Unterminated literals end with the line.
+fn quoted_len(source: &str, quote: char) -> usize {
+    let mut chars = source.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '\n' => return index,
+            c if c == quote => return index + 1,
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+/// Returns the length of the leading characters of `source` matching
+/// `predicate`
+fn leading_len(source: &str, predicate: impl Fn(char) -> bool) -> usize {
+    source.find(|c| !predicate(c)).unwrap_or(source.len())
+}
+
+/// Returns the kind and length of the token at the start of `source`
+fn next_token(source: &str) -> (TokenKind, usize) {
+    let first = source.chars().next().unwrap();
+    if source.starts_with("//") {
+        let len = source.find('\n').unwrap_or(source.len());
+        (TokenKind::Comment, len)
+    } else if let Some(rest) = source.strip_prefix("/*") {
+        let len = rest.find("*/").map_or(source.len(), |end| end + 4);
+        (TokenKind::Comment, len)
+    } else if let Some(rest) = source.strip_prefix("\"\"\"") {
+        // Java text blocks and Kotlin and Scala raw strings
+        let len = rest.find("\"\"\"").map_or(source.len(), |end| end + 6);
+        (TokenKind::String, len)
+    } else if first == '"' || first == '\'' {
+        (TokenKind::String, quoted_len(source, first))
+    } else if first.is_whitespace() {
+        (
+            TokenKind::Whitespace,
+            leading_len(source, char::is_whitespace),
+        )
+    } else if is_identifier_start(first) {
+        (
+            TokenKind::Identifier,
+            leading_len(source, is_identifier_char),
+        )
+    } else if first.is_ascii_digit() {
+        // Digits, suffixes and exponents, and decimal points followed by a
+        // digit (e.g., `1.5f` but not the `.` in `1.toString()`)
+        let mut len = leading_len(source, is_identifier_char);
+        while source[len..].starts_with('.')
+            && source[len + 1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            len += 1 + leading_len(&source[len + 1..], is_identifier_char);
+        }
+        (TokenKind::Number, len)
+    } else {
+        (TokenKind::Symbol, first.len_utf8())
+    }
+}
+
+/// Splits Java, Kotlin or Scala source into tokens
+pub fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while !rest.is_empty() {
+        let (kind, len) = next_token(rest);
+        tokens.push(Token {
+            kind,
+            text: &rest[..len],
+        });
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(source)
+            .into_iter()
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let source = "import a.b; // c \"d\"\nval e = \"f \\\" g\" + 'h' /* i\n j */ 1.5f";
+        assert_eq!(
+            kinds(source),
+            vec![
+                (TokenKind::Identifier, "import"),
+                (TokenKind::Identifier, "a"),
+                (TokenKind::Symbol, "."),
+                (TokenKind::Identifier, "b"),
+                (TokenKind::Symbol, ";"),
+                (TokenKind::Comment, "// c \"d\""),
+                (TokenKind::Identifier, "val"),
+                (TokenKind::Identifier, "e"),
+                (TokenKind::Symbol, "="),
+                (TokenKind::String, "\"f \\\" g\""),
+                (TokenKind::Symbol, "+"),
+                (TokenKind::String, "'h'"),
+                (TokenKind::Comment, "/* i\n j */"),
+                (TokenKind::Number, "1.5f"),
+            ]
+        );
+        let text = tokenize(source)
+            .iter()
+            .map(|token| token.text)
+            .collect::<String>();
+        assert_eq!(text, source);
+    }
+
+    #[test]
+    fn test_tokenize_text_block() {
+        assert_eq!(
+            kinds("\"\"\"\n  a \"b\"\n\"\"\".trim()"),
+            vec![
+                (TokenKind::String, "\"\"\"\n  a \"b\"\n\"\"\""),
+                (TokenKind::Symbol, "."),
+                (TokenKind::Identifier, "trim"),
+                (TokenKind::Symbol, "("),
+                (TokenKind::Symbol, ")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_unterminated() {
+        assert_eq!(
+            kinds("\"a\nb /* c"),
+            vec![
+                (TokenKind::String, "\"a"),
+                (TokenKind::Identifier, "b"),
+                (TokenKind::Comment, "/* c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_number_member() {
+        assert_eq!(
+            kinds("1.toString()")[..3],
+            [
+                (TokenKind::Number, "1"),
+                (TokenKind::Symbol, "."),
+                (TokenKind::Identifier, "toString"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_java_identifier() {
+        for name in ["TitleScreenMixin", "_init", "$a1", "onInit"] {
+            assert!(is_java_identifier(name), "{}", name);
+        }
+        for name in ["", "1Mixin", "<init>", "a-b", "class", "_"] {
+            assert!(!is_java_identifier(name), "{}", name);
+        }
+    }
+}
@@ -1,8 +1,14 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::file;
+use crate::{
+    file,
+    plan::{self, Plan},
+};
 
-use super::language::Language;
+use super::{
+    language::Language,
+    lexer::{self, Token, TokenKind},
+};
 
 #[derive(Debug)]
 pub struct Error {
@@ -31,60 +37,204 @@ impl From<file::Error> for Error {
     }
 }
 
-pub fn rename_package(
+impl From<plan::Error> for Error {
+    fn from(error: plan::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Source roots of the source sets (the client one only exists with split
+/// environment source sets)
+pub const SOURCE_ROOTS: [&str; 2] = ["src/main", "src/client"];
+
+/// Returns the module of the language in each source root that has one
+pub fn module_paths(path: &Path, language: &Language) -> Vec<PathBuf> {
+    SOURCE_ROOTS
+        .iter()
+        .map(|root| path.join(root).join(language.source_dir()))
+        .filter(|module_path| module_path.is_dir())
+        .collect()
+}
+
+/// Returns the source file of `class` in any source root
+pub fn find_class(path: &Path, language: &Language, class: &str) -> Option<PathBuf> {
+    let class_file = class.replace('.', "/") + "." + language.extension();
+    module_paths(path, language)
+        .iter()
+        .map(|base_path| base_path.join(&class_file))
+        .find(|class_path| class_path.exists())
+}
+
+/// Replaces each identifier `old` in `source` with `new`. Comments, strings
+/// and longer identifiers containing `old` are left untouched.
+pub fn rename_identifier(source: &str, old: &str, new: &str) -> String {
+    lexer::tokenize(source)
+        .iter()
+        .map(|token| match token.kind {
+            TokenKind::Identifier if token.text == old => new,
+            _ => token.text,
+        })
+        .collect()
+}
+
+/// Skips the whitespace and comments from `index`
+fn skip_trivia(tokens: &[Token], mut index: usize) -> usize {
+    while tokens
+        .get(index)
+        .is_some_and(|token| matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+    {
+        index += 1;
+    }
+    index
+}
+
+/// Returns the index after the qualified name made of `parts` (e.g., `net`,
+/// `fabricmc` and `example`) starting at `start`, if there is one
+fn match_qualified_name(tokens: &[Token], start: usize, parts: &[&str]) -> Option<usize> {
+    let mut index = start;
+    for (n, part) in parts.iter().enumerate() {
+        if n > 0 {
+            index = skip_trivia(tokens, index);
+            if tokens.get(index)?.text != "." {
+                return None;
+            }
+            index = skip_trivia(tokens, index + 1);
+        }
+        let token = tokens.get(index)?;
+        if token.kind != TokenKind::Identifier || token.text != *part {
+            return None;
+        }
+        index += 1;
+    }
+    Some(index)
+}
+
+/// Replaces each qualified name starting with `old` (e.g., a package in
+/// package declarations, imports and fully qualified references) with `new`.
+/// Names that merely contain `old` (e.g., `a.net.fabricmc.example`) or
+/// continue its last part (e.g., `net.fabricmc.example2`), comments and
+/// strings are left untouched.
+pub fn rename_qualified_name(source: &str, old: &str, new: &str) -> String {
+    let tokens = lexer::tokenize(source);
+    let parts = old.split('.').collect::<Vec<_>>();
+    let mut result = String::new();
+    let mut after_dot = false;
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = tokens[index];
+        if token.kind == TokenKind::Identifier && !after_dot {
+            if let Some(end) = match_qualified_name(&tokens, index, &parts) {
+                result.push_str(new);
+                index = end;
+                continue;
+            }
+        }
+
+        if !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment) {
+            after_dot = token.text == ".";
+        }
+        result.push_str(token.text);
+        index += 1;
+    }
+    result
+}
+
+/// Moves `name`, a package or a qualified name inside `old_package`, to
+/// `new_package`. Returns `None` if `name` isn't inside `old_package` (e.g.,
+/// `net.fabricmc.example2.A` isn't inside `net.fabricmc.example`).
+pub fn move_to_package(name: &str, old_package: &str, new_package: &str) -> Option<String> {
+    let suffix = name.strip_prefix(old_package)?;
+    if suffix.is_empty() || suffix.starts_with('.') {
+        Some(format!("{}{}", new_package, suffix))
+    } else {
+        None
+    }
+}
+
+/// Rewrites each source file under `path` with `rewrite`
+fn rewrite_sources(
+    plan: &mut Plan,
     path: &Path,
+    rewrite: impl Fn(&str) -> String,
+) -> Result<(), Error> {
+    for file in file::list_files(path)? {
+        let extension = file.extension().unwrap_or_default();
+        if !Language::all().any(|language| extension == language.extension()) {
+            continue;
+        }
+
+        let file_path = path.join(file);
+        let source = std::fs::read_to_string(&file_path)?;
+        let rewritten = rewrite(&source);
+        if rewritten != source {
+            plan.write(&file_path, rewritten)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn rename_package(
+    plan: &mut Plan,
     language: &Language,
     old_package: &str,
     new_package: &str,
 ) -> Result<(), Error> {
-    // Move the entrypoint to the correct location
-    let base_path = path.join("src/main").join(language.to_string());
-
-    let old_package_path = base_path.join(old_package.replace(".", "/"));
-    let new_package_path = base_path.join(new_package.replace(".", "/"));
-
-    // Create the new package directory
-    std::fs::create_dir_all(&new_package_path)?;
+    if old_package == new_package {
+        return Ok(());
+    }
 
-    // Move the old package directory to the new package directory
-    std::fs::rename(&old_package_path, &new_package_path)?;
+    let base_paths = module_paths(plan.root(), language);
+    for base_path in &base_paths {
+        let old_package_path = base_path.join(old_package.replace('.', "/"));
+        let new_package_path = base_path.join(new_package.replace('.', "/"));
+        if !old_package_path.exists() {
+            continue;
+        }
 
-    // Remove the old package directory
-    file::remove_empty_parent_dirs(&old_package_path)?;
+        // Move the old package directory to the new package directory,
+        // removing the old one
+        plan.rename(&old_package_path, &new_package_path)?;
+    }
 
     // Update the package name in each source file
-    file::recursive_replace(&base_path, old_package, new_package)?;
+    for base_path in &base_paths {
+        rewrite_sources(plan, base_path, |source| {
+            rename_qualified_name(source, old_package, new_package)
+        })?;
+    }
 
     Ok(())
 }
 
+/// Moves the source file of `old_class` to `new_class`, in the same source
+/// root, and renames the class in each source file
 pub fn rename_class(
-    path: &Path,
+    plan: &mut Plan,
     language: &Language,
     old_class: &str,
     new_class: &str,
 ) -> Result<(), Error> {
-    // Move the entrypoint to the correct location
-    let base_path = path.join("src/main").join(language.to_string());
+    let old_class_path = find_class(plan.root(), language, old_class).ok_or_else(|| Error {
+        message: format!("Source file of {} not found", old_class),
+    })?;
+    // Keep the class in the same source root
+    let depth = old_class.split('.').count();
+    let base_path = old_class_path.ancestors().nth(depth).unwrap();
+    let new_class_path = base_path.join(new_class.replace('.', "/") + "." + language.extension());
 
-    let old_class_path = base_path.join(old_class.replace(".", "/") + "." + language.extension());
-    let new_class_path = base_path.join(new_class.replace(".", "/") + "." + language.extension());
-
-    // Create the directory if it doesn't exist
-    if let Some(parent) = new_class_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    // Rename the file
-    std::fs::rename(&old_class_path, &new_class_path)?;
-
-    // Remove the old package directory if it's empty
-    file::remove_empty_parent_dirs(&old_class_path)?;
+    // Move the file, removing the old package directory if it's empty
+    plan.rename(&old_class_path, &new_class_path)?;
 
     // Update the class name in each source file
-    let old_class_name = old_class.split('.').last().unwrap();
-    let new_class_name = new_class.split('.').last().unwrap();
-    file::recursive_replace(&base_path, old_class_name, new_class_name)?;
+    let old_class_name = old_class.split('.').next_back().unwrap();
+    let new_class_name = new_class.split('.').next_back().unwrap();
+    for base_path in &module_paths(plan.root(), language) {
+        rewrite_sources(plan, base_path, |source| {
+            rename_identifier(source, old_class_name, new_class_name)
+        })?;
+    }
 
     Ok(())
 }
@@ -108,14 +258,15 @@ mod tests {
     }
 
     #[rstest]
-    #[case(Language::Java)]
-    #[case(Language::Kotlin)]
+    #[case(Language::JAVA)]
+    #[case(Language::KOTLIN)]
+    #[case(Language::SCALA)]
     fn test_rename_package(#[case] language: Language) {
         let temp_dir = tempfile::tempdir().unwrap();
         let old_file = temp_dir
             .path()
             .join("src/main")
-            .join(language.to_string())
+            .join(language.source_dir())
             .join("net/fabricmc/example/ExampleMod.".to_string() + language.extension());
 
         create_text_file(
@@ -126,7 +277,7 @@ public class ExampleMod {}",
         );
 
         rename_package(
-            &temp_dir.path(),
+            &mut Plan::new(temp_dir.path()),
             &language,
             "net.fabricmc.example",
             "com.example",
@@ -136,7 +287,7 @@ public class ExampleMod {}",
         let new_file = temp_dir
             .path()
             .join("src/main")
-            .join(language.to_string())
+            .join(language.source_dir())
             .join("com/example/ExampleMod.".to_string() + language.extension());
 
         let content = fs::read_to_string(&new_file).unwrap();
@@ -150,20 +301,56 @@ public class ExampleMod {}"
         let old_package_root = temp_dir
             .path()
             .join("src/main")
-            .join(language.to_string())
+            .join(language.source_dir())
             .join("net");
         assert!(!old_package_root.exists());
     }
 
+    #[test]
+    fn test_rename_package_in_client_source_set() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_file = temp_dir
+            .path()
+            .join("src/main/java/net/fabricmc/example/ExampleMod.java");
+        let client_file = temp_dir
+            .path()
+            .join("src/client/java/net/fabricmc/example/ExampleModClient.java");
+        create_text_file(&main_file, "package net.fabricmc.example;");
+        create_text_file(&client_file, "package net.fabricmc.example;");
+
+        rename_package(
+            &mut Plan::new(temp_dir.path()),
+            &Language::JAVA,
+            "net.fabricmc.example",
+            "com.example",
+        )
+        .unwrap();
+
+        for (root, class) in [
+            ("src/main", "ExampleMod"),
+            ("src/client", "ExampleModClient"),
+        ] {
+            let new_file = temp_dir
+                .path()
+                .join(root)
+                .join("java/com/example")
+                .join(format!("{}.java", class));
+            let content = fs::read_to_string(new_file).unwrap();
+            assert_eq!(content, "package com.example;");
+        }
+        assert!(!temp_dir.path().join("src/client/java/net").exists());
+    }
+
     #[rstest]
-    #[case(Language::Java)]
-    #[case(Language::Kotlin)]
+    #[case(Language::JAVA)]
+    #[case(Language::KOTLIN)]
+    #[case(Language::SCALA)]
     fn test_rename_class(#[case] language: Language) {
         let temp_dir = tempfile::tempdir().unwrap();
         let old_file = temp_dir
             .path()
             .join("src/main")
-            .join(language.to_string())
+            .join(language.source_dir())
             .join("net/fabricmc/example/ExampleMod.".to_string() + language.extension());
 
         create_text_file(
@@ -174,7 +361,7 @@ public class ExampleMod {}",
         );
 
         rename_class(
-            &temp_dir.path(),
+            &mut Plan::new(temp_dir.path()),
             &language,
             "net.fabricmc.example.ExampleMod",
             "com.example.ExampleMod2",
@@ -184,7 +371,7 @@ public class ExampleMod {}",
         let new_file = temp_dir
             .path()
             .join("src/main")
-            .join(language.to_string())
+            .join(language.source_dir())
             .join("com/example/ExampleMod2.".to_string() + language.extension());
 
         let content = fs::read_to_string(&new_file).unwrap();
@@ -195,4 +382,73 @@ public class ExampleMod {}",
 public class ExampleMod2 {}"
         );
     }
+
+    #[test]
+    fn test_rename_identifier() {
+        let source = "// ExampleMod\nclass ExampleMod : ExampleModBase {\n    val name = \"ExampleMod\"\n    val client = ExampleModClient()\n    val mod = net.fabricmc.example.ExampleMod()\n}\n";
+        assert_eq!(
+            rename_identifier(source, "ExampleMod", "ExampleMod2"),
+            "// ExampleMod\nclass ExampleMod2 : ExampleModBase {\n    val name = \"ExampleMod\"\n    val client = ExampleModClient()\n    val mod = net.fabricmc.example.ExampleMod2()\n}\n"
+        );
+    }
+
+    #[rstest]
+    #[case("package net.fabricmc.example;", "package com.example;")]
+    #[case("import net.fabricmc.example.mixin.A;", "import com.example.mixin.A;")]
+    #[case("import net.fabricmc.example.*;", "import com.example.*;")]
+    #[case("new net . fabricmc . example.A()", "new com.example.A()")]
+    #[case("import net.fabricmc.example2.A;", "import net.fabricmc.example2.A;")]
+    #[case("import net.fabricmc.examples.A;", "import net.fabricmc.examples.A;")]
+    #[case("a.net.fabricmc.example.A", "a.net.fabricmc.example.A")]
+    #[case("// net.fabricmc.example", "// net.fabricmc.example")]
+    #[case("\"net.fabricmc.example\"", "\"net.fabricmc.example\"")]
+    fn test_rename_qualified_name(#[case] source: &str, #[case] expected: &str) {
+        assert_eq!(
+            rename_qualified_name(source, "net.fabricmc.example", "com.example"),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("net.fabricmc.example", Some("com.example"))]
+    #[case("net.fabricmc.example.mixin", Some("com.example.mixin"))]
+    #[case("net.fabricmc.example.client.A", Some("com.example.client.A"))]
+    #[case("net.fabricmc.example2.A", None)]
+    #[case("net.fabricmc.examples", None)]
+    #[case("net.fabricmc", None)]
+    fn test_move_to_package(#[case] name: &str, #[case] expected: Option<&str>) {
+        assert_eq!(
+            move_to_package(name, "net.fabricmc.example", "com.example").as_deref(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_rename_class_keeps_longer_names() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let java = temp_dir.path().join("src/main/java/net/fabricmc/example");
+        create_text_file(
+            &java.join("ExampleMod.java"),
+            "package net.fabricmc.example;\n\n// The ExampleMod entrypoint\npublic class ExampleMod {}\n",
+        );
+        let mixin = "package net.fabricmc.example.mixin;\n\npublic class ExampleModMixin {}\n";
+        create_text_file(&java.join("mixin/ExampleModMixin.java"), mixin);
+
+        rename_class(
+            &mut Plan::new(temp_dir.path()),
+            &Language::JAVA,
+            "net.fabricmc.example.ExampleMod",
+            "net.fabricmc.example.Tweaks",
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(java.join("Tweaks.java")).unwrap(),
+            "package net.fabricmc.example;\n\n// The ExampleMod entrypoint\npublic class Tweaks {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(java.join("mixin/ExampleModMixin.java")).unwrap(),
+            mixin
+        );
+    }
 }
@@ -1,8 +1,24 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::{
-    code::{language::Language, refactor},
-    file, git,
+    access_widener,
+    cache::Cache,
+    code::{
+        entrypoint::{self, EntrypointKind},
+        language::{Language, CLIENT_CLASS, DATAGEN_CLASS},
+        refactor,
+    },
+    file, git, gradle,
+    lockfile::{self, Lockfile},
+    meta::{self, Meta, Versions},
+    minecraft::{MinecraftVersion, Stage},
+    plan::{self, Plan},
+    staging::{self, Staging},
+    template::{
+        self,
+        manifest::{self, Manifest},
+        Template,
+    },
 };
 
 #[derive(Debug)]
@@ -24,257 +40,1936 @@ impl From<git::Error> for Error {
     }
 }
 
+impl From<template::Error> for Error {
+    fn from(error: template::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<manifest::Error> for Error {
+    fn from(error: manifest::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<access_widener::Error> for Error {
+    fn from(error: access_widener::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<lockfile::Error> for Error {
+    fn from(error: lockfile::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<meta::Error> for Error {
+    fn from(error: meta::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(error: std::io::Error) -> Self {
         Error {
             message: error.to_string(),
         }
     }
-}
+}
+
+impl From<refactor::Error> for Error {
+    fn from(error: refactor::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<plan::Error> for Error {
+    fn from(error: plan::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<staging::Error> for Error {
+    fn from(error: staging::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<file::Error> for Error {
+    fn from(error: file::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// The fabric.mod.json entry of an entrypoint. Entrypoints loaded by a
+/// language adapter are objects.
+fn entrypoint_entry(language: &Language, kind: EntrypointKind, class: &str) -> serde_json::Value {
+    let value = kind.value(class);
+    match language.backend().adapter {
+        Some(adapter) if kind != EntrypointKind::Class => {
+            serde_json::json!({ "adapter": adapter, "value": value })
+        }
+        _ => serde_json::Value::String(value),
+    }
+}
+
+/// Reads the class of the template's client entrypoint
+fn read_client_class(path: &Path) -> Result<Option<String>, Error> {
+    let config_path = path.join("src/main/resources/fabric.mod.json");
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(config_path)?)?;
+    let entrypoint = &config["entrypoints"]["client"][0];
+    Ok(entrypoint
+        .as_str()
+        .or_else(|| entrypoint["value"].as_str())
+        .map(|class| class.to_string()))
+}
+
+fn update_mod_config(
+    plan: &mut Plan,
+    options: &Options,
+    client_class: Option<&str>,
+) -> Result<(), Error> {
+    let mod_id = &options.mod_id;
+    let language = &options.language;
+    let config_path = plan.path("src/main/resources/fabric.mod.json");
+    let mut config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+
+    config["id"] = serde_json::Value::String(mod_id.to_string());
+    config["name"] = serde_json::Value::String(options.name.clone());
+    config["description"] = serde_json::Value::String("".to_string());
+    config["icon"] = serde_json::Value::String(format!("assets/{}/icon.png", mod_id));
+
+    // Keep the template's adapter for classes
+    let backend = language.backend();
+    let entrypoint_kind = options.entrypoint_kind();
+    let entrypoint = &mut config["entrypoints"]["main"][0];
+    if entrypoint_kind == EntrypointKind::Class && entrypoint.is_object() {
+        entrypoint["value"] = serde_json::Value::String(options.main_class.clone());
+    } else {
+        *entrypoint = entrypoint_entry(language, entrypoint_kind, &options.main_class);
+    }
+
+    if let Some(client_class) = client_class {
+        config["entrypoints"]["client"] =
+            serde_json::json!([entrypoint_entry(language, backend.entrypoint, client_class)]);
+    }
+
+    if options.datagen {
+        let datagen_class = options.datagen_class();
+        config["entrypoints"]["fabric-datagen"] = serde_json::json!([entrypoint_entry(
+            language,
+            backend.entrypoint,
+            &datagen_class
+        )]);
+    }
+
+    // Drop the entrypoints that don't run in the environment. Client-only mods
+    // run the main initializer as a client one.
+    if let Some(entrypoints) = config["entrypoints"].as_object_mut() {
+        match options.environment {
+            Environment::Client => {
+                entrypoints.remove("server");
+                if let Some(serde_json::Value::Array(mut main)) = entrypoints.remove("main") {
+                    if let Some(client) = entrypoints
+                        .get("client")
+                        .and_then(|client| client.as_array())
+                    {
+                        main.extend(client.iter().cloned());
+                    }
+                    entrypoints.insert("client".to_string(), serde_json::Value::Array(main));
+                }
+            }
+            Environment::Server => {
+                entrypoints.remove("client");
+            }
+            Environment::Both => {}
+        }
+    }
+    config["environment"] = serde_json::Value::String(options.environment.field().to_string());
+
+    // Depend on the mod providing the language adapter
+    if let Some(library) = backend.library {
+        if config["depends"].get(library).is_none() {
+            config["depends"][library] = serde_json::Value::String("*".to_string());
+        }
+    }
+
+    plan.write_json(&config_path, &config)?;
+    Ok(())
+}
+
+/// Removes the mixin configs and the sections of the mixin configs (e.g.,
+/// `client`) that don't apply to the environment
+fn update_mixin_environment(plan: &mut Plan, environment: Environment) -> Result<(), Error> {
+    let excluded = match environment {
+        Environment::Client => "server",
+        Environment::Server => "client",
+        Environment::Both => return Ok(()),
+    };
+
+    let config_path = plan.path("src/main/resources/fabric.mod.json");
+    let mut config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+    if let Some(mixins) = config["mixins"].as_array_mut() {
+        for entry in mixins.iter() {
+            let name = entry.as_str().or_else(|| entry["config"].as_str());
+            let mixin_path = match name.and_then(|name| find_resource(plan.root(), name)) {
+                Some(mixin_path) => mixin_path,
+                None => continue,
+            };
+
+            if entry["environment"] == excluded {
+                plan.delete(&mixin_path)?;
+                continue;
+            }
+            let mut mixin: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&mixin_path)?)?;
+            let removed = mixin
+                .as_object_mut()
+                .and_then(|mixin| mixin.remove(excluded));
+            if removed.is_some() {
+                plan.write_json(&mixin_path, &mixin)?;
+            }
+        }
+        mixins.retain(|entry| entry["environment"] != excluded);
+    }
+
+    plan.write_json(&config_path, &config)?;
+    Ok(())
+}
+
+/// Generates IDE run configurations only for the environment the mod runs in
+fn update_run_configs(plan: &mut Plan, environment: Environment) -> Result<(), Error> {
+    let build_path = plan.path("build.gradle");
+    let disabled = match environment {
+        Environment::Client => "server",
+        Environment::Server => "client",
+        Environment::Both => return Ok(()),
+    };
+    if build_path.exists() {
+        let mut build = std::fs::read_to_string(&build_path)?;
+        gradle::disable_run_config(&mut build, disabled);
+        plan.write(&build_path, build)?;
+    }
+    Ok(())
+}
+
+/// Returns the path of a resource in any of the source sets
+pub fn find_resource(path: &Path, name: &str) -> Option<PathBuf> {
+    ["src/main/resources", "src/client/resources"]
+        .iter()
+        .map(|resources| path.join(resources).join(name))
+        .find(|path| path.exists())
+}
+
+fn update_mixin_config(
+    plan: &mut Plan,
+    path: &Path,
+    old_package: &str,
+    new_package: &str,
+) -> Result<(), Error> {
+    let mut config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    if let Some(package) = config["package"].as_str() {
+        if let Some(package) = refactor::move_to_package(package, old_package, new_package) {
+            config["package"] = serde_json::Value::String(package);
+        }
+    }
+    plan.write_json(path, &config)?;
+    Ok(())
+}
+
+/// Renames each mixin config declared in fabric.mod.json after the mod ID and
+/// moves its package along with the main package
+pub fn update_mixin_configs(
+    plan: &mut Plan,
+    manifest: &Manifest,
+    mod_id: &str,
+    new_package: &str,
+) -> Result<(), Error> {
+    let config_path = plan.path("src/main/resources/fabric.mod.json");
+    let mut config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+
+    if let Some(mixins) = config["mixins"].as_array_mut() {
+        for entry in mixins {
+            // Entries are either a file name or an object with a "config" key
+            let value = if entry.is_string() {
+                entry
+            } else {
+                &mut entry["config"]
+            };
+            let old_name = match value.as_str() {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let old_path = find_resource(plan.root(), &old_name).ok_or_else(|| Error {
+                message: format!("Mixin config not found: {}", old_name),
+            })?;
+
+            let new_name = old_name.replace(&manifest.mod_id, mod_id);
+            let new_path = old_path.with_file_name(&new_name);
+            plan.rename(&old_path, &new_path)?;
+            *value = serde_json::Value::String(new_name);
+
+            update_mixin_config(plan, &new_path, &manifest.package, new_package)?;
+        }
+    }
+
+    plan.write_json(&config_path, &config)?;
+    Ok(())
+}
+
+fn update_gradle_properties(plan: &mut Plan, group: &str, base_name: &str) -> Result<(), Error> {
+    let config_path = plan.path("gradle.properties");
+    let mut config = std::fs::read_to_string(&config_path)?;
+    gradle::set_property(&mut config, "maven_group", group);
+    gradle::set_property(&mut config, "archives_base_name", base_name);
+    plan.write(&config_path, config)?;
+    Ok(())
+}
+
+/// Pins the exact Minecraft version and the dependency versions resolved for
+/// it in gradle.properties and build.gradle (Loom, unless its version is a
+/// property)
+fn update_dependency_versions(
+    plan: &mut Plan,
+    minecraft_version: &MinecraftVersion,
+    versions: &Versions,
+) -> Result<(), Error> {
+    let properties_path = plan.path("gradle.properties");
+    let mut properties = std::fs::read_to_string(&properties_path)?;
+    gradle::set_property(
+        &mut properties,
+        "minecraft_version",
+        &minecraft_version.to_string(),
+    );
+    for (key, version) in [
+        ("yarn_mappings", &versions.yarn_mappings),
+        ("loader_version", &versions.loader_version),
+        ("fabric_version", &versions.fabric_version),
+    ] {
+        match version {
+            Some(version) => {
+                gradle::set_property(&mut properties, key, version);
+            }
+            None => eprintln!(
+                "No {} found for Minecraft {}, keeping the template's",
+                key, minecraft_version
+            ),
+        }
+    }
+
+    if let Some(loom_version) = &versions.loom_version {
+        let build_path = plan.path("build.gradle");
+        if !gradle::set_property(&mut properties, "loom_version", loom_version)
+            && build_path.exists()
+        {
+            let mut build = std::fs::read_to_string(&build_path)?;
+            gradle::set_plugin_version(&mut build, "fabric-loom", loom_version);
+            plan.write(&build_path, build)?;
+        }
+    }
+    plan.write(&properties_path, properties)?;
+    Ok(())
+}
+
+/// Warns if build.gradle doesn't apply the plugin compiling the language
+fn check_language_plugin(path: &Path, language: &Language) -> Result<(), Error> {
+    let build_path = path.join("build.gradle");
+    if let (Some(plugin), true) = (language.backend().plugin, build_path.exists()) {
+        if !gradle::applies_plugin(&std::fs::read_to_string(build_path)?, plugin) {
+            eprintln!(
+                "Warning: build.gradle doesn't apply the {} plugin needed for {}",
+                plugin,
+                language.to_string()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Turns the main class into a `ClientModInitializer`, for client-only mods.
+/// Top-level functions don't implement an interface, so they are left alone.
+fn to_client_initializer(
+    plan: &mut Plan,
+    language: &Language,
+    main_class: &str,
+    entrypoint_kind: EntrypointKind,
+) -> Result<(), Error> {
+    if entrypoint_kind == EntrypointKind::Function {
+        return Ok(());
+    }
+    if let Some(class_path) = refactor::find_class(plan.root(), language, main_class) {
+        let source = std::fs::read_to_string(&class_path)?;
+        plan.write(&class_path, entrypoint::to_client_initializer(&source))?;
+    }
+    Ok(())
+}
+
+/// Writes `source`, declaring `template_class`, as `class` under the source
+/// root `root` (e.g., `src/main`)
+fn write_class(
+    plan: &mut Plan,
+    root: &str,
+    language: &Language,
+    source: &str,
+    template_class: &str,
+    class: &str,
+) -> Result<(), Error> {
+    let (package, name) = class.rsplit_once('.').unwrap_or(("", class));
+    let (template_package, template_name) = template_class.rsplit_once('.').unwrap();
+    let source = refactor::rename_qualified_name(source, template_package, package);
+    let source = refactor::rename_identifier(&source, template_name, name);
+
+    let class_path = plan.path(root).join(language.source_dir()).join(format!(
+        "{}.{}",
+        class.replace('.', "/"),
+        language.extension()
+    ));
+    plan.write(&class_path, source)?;
+    Ok(())
+}
+
+/// Writes a new client entrypoint, in the client source set if there is one
+fn write_client_class(
+    plan: &mut Plan,
+    language: &Language,
+    client_class: &str,
+    split_sources: bool,
+) -> Result<(), Error> {
+    let root = if split_sources || plan.path("src/client").exists() {
+        "src/client"
+    } else {
+        "src/main"
+    };
+    let source = language.backend().client;
+    write_class(plan, root, language, source, CLIENT_CLASS, client_class)
+}
+
+/// Writes a data generation entrypoint next to the main class, sets up data
+/// generation in build.gradle and creates the directory the generated
+/// resources are written to
+fn add_datagen(plan: &mut Plan, options: &Options) -> Result<(), Error> {
+    let language = &options.language;
+    let source = language.backend().datagen;
+    let datagen_class = options.datagen_class();
+    write_class(
+        plan,
+        "src/main",
+        language,
+        source,
+        DATAGEN_CLASS,
+        &datagen_class,
+    )?;
+
+    let build_path = plan.path("build.gradle");
+    if build_path.exists() {
+        let mut build = std::fs::read_to_string(&build_path)?;
+        if gradle::configure_data_generation(&mut build) {
+            plan.write(&build_path, build)?;
+        }
+    }
+
+    // Keep the directory in git until resources are generated
+    let generated = plan.path("src/main/generated");
+    plan.write(&generated.join(".gitkeep"), "")?;
+    Ok(())
+}
+
+/// Renames the template's client entrypoint along with the main class, or
+/// writes a new one if `--client-entrypoint` is given. Returns the class of the
+/// client entrypoint, if any.
+fn update_client_class(
+    plan: &mut Plan,
+    options: &Options,
+    manifest: &Manifest,
+    template_client_class: Option<String>,
+) -> Result<Option<String>, Error> {
+    let (new_package, new_main_name) = options.main_class.rsplit_once('.').unwrap();
+    let old_class = template_client_class
+        .and_then(|class| refactor::move_to_package(&class, &manifest.package, new_package));
+    let old_class = match old_class {
+        Some(old_class) => old_class,
+        None => {
+            if let Some(client_class) = &options.client_class {
+                write_client_class(plan, &options.language, client_class, options.split_sources)?;
+            }
+            return Ok(options.client_class.clone());
+        }
+    };
+
+    // The client class is named after the main class (e.g., `ExampleModClient`
+    // becomes `ExampleMod2Client`)
+    let (old_package, old_name) = old_class.rsplit_once('.').unwrap();
+    let renamed = old_name.replace(&manifest.main_class, new_main_name);
+    let new_class = options
+        .client_class
+        .clone()
+        .unwrap_or_else(|| format!("{}.{}", old_package, renamed));
+    let client_package = new_class
+        .rsplit_once('.')
+        .map_or("", |(package, _)| package);
+    for language in options.language.modules() {
+        if refactor::find_class(plan.root(), &language, &old_class).is_none() {
+            continue;
+        }
+        refactor::rename_class(plan, &language, &old_class, &new_class)?;
+
+        // Update the package declaration if the class moved to another package
+        if client_package != old_package {
+            let class_path = refactor::find_class(plan.root(), &language, &new_class).unwrap();
+            let source = std::fs::read_to_string(&class_path)?;
+            plan.write(
+                &class_path,
+                source.replacen(
+                    &format!("package {}", old_package),
+                    &format!("package {}", client_package),
+                    1,
+                ),
+            )?;
+        }
+    }
+    Ok(Some(new_class))
+}
+
+/// Sets up Loom's split environment source sets, with a client mixin config
+fn split_source_sets(
+    plan: &mut Plan,
+    mod_id: &str,
+    package: &str,
+    minecraft_version: &MinecraftVersion,
+) -> Result<(), Error> {
+    let build_path = plan.path("build.gradle");
+    if build_path.exists() {
+        let mut build = std::fs::read_to_string(&build_path)?;
+        if gradle::split_environment_source_sets(&mut build, mod_id) {
+            plan.write(&build_path, build)?;
+        }
+    }
+
+    // Add a client mixin config, unless the template already has one
+    let config_path = plan.path("src/main/resources/fabric.mod.json");
+    let mut config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+    let has_client_mixins = config["mixins"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|entry| entry["environment"] == "client");
+    if has_client_mixins {
+        return Ok(());
+    }
+
+    let name = format!("{}.client.mixins.json", mod_id);
+    let mixin_path = plan.path("src/client/resources").join(&name);
+    let mixin = serde_json::json!({
+        "required": true,
+        "minVersion": "0.8",
+        "package": format!("{}.mixin.client", package),
+        "compatibilityLevel": format!("JAVA_{}", minecraft_version.java_version()),
+        "client": [],
+        "injectors": {
+            "defaultRequire": 1
+        }
+    });
+    plan.write_json(&mixin_path, &mixin)?;
+
+    let entry = serde_json::json!({ "config": name, "environment": "client" });
+    match config["mixins"].as_array_mut() {
+        Some(mixins) => mixins.push(entry),
+        None => config["mixins"] = serde_json::json!([entry]),
+    }
+    plan.write_json(&config_path, &config)?;
+    Ok(())
+}
+
+/// Sets the Java version in build.gradle
+fn update_java_version(plan: &mut Plan, java_version: u32) -> Result<(), Error> {
+    let build_path = plan.path("build.gradle");
+    if build_path.exists() {
+        let mut build = std::fs::read_to_string(&build_path)?;
+        gradle::set_java_version(&mut build, java_version);
+        plan.write(&build_path, build)?;
+    }
+    Ok(())
+}
+
+/// Sets the Minecraft and Java dependencies in fabric.mod.json and the
+/// compatibility level of the mixin configs it declares
+pub fn update_version_fields(
+    plan: &mut Plan,
+    minecraft_version: &MinecraftVersion,
+) -> Result<(), Error> {
+    let java_version = minecraft_version.java_version();
+    let config_path = plan.path("src/main/resources/fabric.mod.json");
+    let mut config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+
+    if config["depends"]["minecraft"].is_string() {
+        // Snapshots and pre-releases don't match version ranges
+        let dependency = match minecraft_version.stage {
+            Stage::Release => format!("~{}", minecraft_version),
+            _ => minecraft_version.to_string(),
+        };
+        config["depends"]["minecraft"] = serde_json::Value::String(dependency);
+    }
+    if config["depends"]["java"].is_string() {
+        config["depends"]["java"] = serde_json::Value::String(format!(">={}", java_version));
+    }
+    plan.write_json(&config_path, &config)?;
+
+    for entry in config["mixins"].as_array().into_iter().flatten() {
+        let name = entry.as_str().or_else(|| entry["config"].as_str());
+        let mixin_path = match name.and_then(|name| find_resource(plan.root(), name)) {
+            Some(mixin_path) => mixin_path,
+            None => continue,
+        };
+
+        let mut mixin: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&mixin_path)?)?;
+        if mixin["compatibilityLevel"].is_string() {
+            mixin["compatibilityLevel"] =
+                serde_json::Value::String(format!("JAVA_{}", java_version));
+            plan.write_json(&mixin_path, &mixin)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn refactor_module(
+    plan: &mut Plan,
+    language: &Language,
+    manifest: &Manifest,
+    main_class: &str,
+) -> Result<(), Error> {
+    // Rename the package
+    let old_package = &manifest.package;
+    let new_package = main_class[..main_class.rfind('.').unwrap()].to_string();
+    refactor::rename_package(plan, language, old_package, &new_package)?;
+
+    // Rename the main class (if contained in this module)
+    let old_class = format!("{}.{}", &new_package, manifest.main_class);
+    if refactor::find_class(plan.root(), language, &old_class).is_some() {
+        let new_class = main_class;
+        refactor::rename_class(plan, language, &old_class, new_class)?;
+    }
+
+    Ok(())
+}
+
+/// Where the mod runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Environment {
+    Client,
+    /// Dedicated servers only
+    Server,
+    Both,
+}
+
+impl Environment {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "client" => Some(Environment::Client),
+            "server" => Some(Environment::Server),
+            "both" => Some(Environment::Both),
+            _ => None,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Environment::Client => "client",
+            Environment::Server => "server",
+            Environment::Both => "both",
+        }
+    }
+
+    /// Value of the `environment` field in fabric.mod.json
+    fn field(self) -> &'static str {
+        match self {
+            Environment::Client => "client",
+            Environment::Server => "server",
+            Environment::Both => "*",
+        }
+    }
+}
+
+/// The inputs a mod is generated from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub mod_id: String,
+    pub name: String,
+    pub main_class: String,
+    pub language: Language,
+    pub minecraft_version: MinecraftVersion,
+    pub template: Template,
+    /// Branch, tag or commit of the template. Defaults to the Minecraft
+    /// version.
+    pub template_ref: Option<String>,
+    /// File or URL with the versions of the dependencies. Defaults to the
+    /// bundled snapshot.
+    pub meta: Option<String>,
+    /// How the main class is declared. Defaults to the language's template.
+    pub entrypoint_kind: Option<EntrypointKind>,
+    /// Class of the client entrypoint. Defaults to the template's, if any.
+    pub client_class: Option<String>,
+    /// Whether to use separate source sets for the client and common code
+    pub split_sources: bool,
+    pub environment: Environment,
+    /// Whether to add a data generation entrypoint
+    pub datagen: bool,
+    /// Whether to add an access widener
+    pub access_widener: bool,
+}
+
+impl Options {
+    /// Class of the data generation entrypoint, next to the main class
+    pub fn datagen_class(&self) -> String {
+        format!("{}DataGenerator", self.main_class)
+    }
+
+    pub fn entrypoint_kind(&self) -> EntrypointKind {
+        self.entrypoint_kind
+            .unwrap_or(self.language.backend().entrypoint)
+    }
+}
+
+/// Rewrites the main class into another kind of entrypoint than the
+/// template's
+fn reshape_entrypoint(
+    plan: &mut Plan,
+    language: &Language,
+    main_class: &str,
+    entrypoint_kind: EntrypointKind,
+) -> Result<(), Error> {
+    let backend = language.backend();
+    let reshape = match backend.reshape {
+        Some(reshape) if entrypoint_kind != backend.entrypoint => reshape,
+        _ => return Ok(()),
+    };
+
+    let class_path = plan
+        .path("src/main")
+        .join(language.source_dir())
+        .join(format!(
+            "{}.{}",
+            main_class.replace('.', "/"),
+            language.extension()
+        ));
+    if !class_path.exists() {
+        return Ok(());
+    }
+    let class_name = main_class.split('.').next_back().unwrap();
+    match reshape(
+        &std::fs::read_to_string(&class_path)?,
+        class_name,
+        entrypoint_kind,
+    ) {
+        Some(source) => plan.write(&class_path, source)?,
+        None => eprintln!(
+            "Warning: could not turn {} into a {}",
+            class_path.display(),
+            entrypoint_kind.to_string()
+        ),
+    }
+    Ok(())
+}
+
+/// Plans the generation of a mod in the working directory `work`, without
+/// touching the mod directory. Progress is reported on stderr, so the plan can
+/// be printed on its own.
+pub fn plan_mod(work: &Path, options: &Options, cache: &Cache) -> Result<Plan, Error> {
+    let mod_id = &options.mod_id;
+    let minecraft_version = &options.minecraft_version;
+    let language = &options.language;
+    let main_class = &options.main_class;
+    let template = &options.template;
+
+    // Load the dependency versions first, so a bad --meta fails early
+    let meta = Meta::load(options.meta.as_deref())?;
+
+    let entrypoint_kind = options.entrypoint_kind();
+    let backend = language.backend();
+    if entrypoint_kind != backend.entrypoint && backend.reshape.is_none() {
+        return Err(Error {
+            message: format!(
+                "The {} entrypoint can't be a {}",
+                language.to_string(),
+                entrypoint_kind.to_string()
+            ),
+        });
+    }
+    if options.environment == Environment::Server
+        && (options.client_class.is_some() || options.split_sources)
+    {
+        return Err(Error {
+            message: "Server-only mods can't have client code".to_string(),
+        });
+    }
+
+    // Check out the template, falling back to the built-in template if the
+    // official one can't be downloaded and the built-in one supports the
+    // version
+    eprintln!("Cloning {}...", template);
+    let mut plan = Plan::new(&work.join("mod"));
+    let checkout = work.join("template");
+    let mut template = template.clone();
+    let template_ref = options.template_ref.as_deref();
+    let fetched = template.fetch(&checkout, minecraft_version, template_ref, cache);
+    let fetched = match fetched {
+        Err(e)
+            if matches!(e.kind(), template::ErrorKind::Unavailable)
+                && template == Template::official(language)
+                && minecraft_version.template_branch() == template::builtin::MINECRAFT_VERSION =>
+        {
+            eprintln!("{}", e.to_string().trim());
+            eprintln!("Falling back to the built-in template...");
+            template = Template::Builtin(*language);
+            template.fetch(&checkout, minecraft_version, None, cache)
+        }
+        fetched => fetched,
+    };
+    fetched.map_err(|e| match (e.kind(), template_ref) {
+        (template::ErrorKind::UnsupportedVersion, Some(template_ref)) => Error {
+            message: format!("Template ref not found: {}", template_ref),
+        },
+        (template::ErrorKind::UnsupportedVersion, None) => {
+            let mut message = format!("Unsupported Minecraft version: {}", minecraft_version);
+            let versions = template.versions(cache).unwrap_or_default();
+            if let Some(nearest) = minecraft_version.nearest(&versions) {
+                message += &format!(" (nearest supported version: {})", nearest);
+            }
+            Error { message }
+        }
+        _ => e.into(),
+    })?;
+
+    // Remember which revision of the template was used
+    let commit = if checkout.join(".git").exists() {
+        let template_repo = git::Context::new(&Some(&checkout))?;
+        Some(
+            template_repo
+                .git(&["rev-parse", "HEAD"])?
+                .trim()
+                .to_string(),
+        )
+    } else {
+        None
+    };
+
+    // Copy the template's files, without its history
+    plan.clone_template(&template.to_string(), &checkout)?;
+
+    // Read the template's placeholders, and remove the manifest from the mod
+    let manifest = Manifest::load(plan.root())?;
+    let template_client_class = read_client_class(plan.root())?;
+    let manifest_path = plan.path(manifest::FILE_NAME);
+    if manifest_path.exists() {
+        plan.delete(&manifest_path)?;
+    }
+
+    eprintln!("Re-initializing git repository...");
+
+    // Remove files that shouldn't end up in the mod (e.g., the license)
+    for file in &manifest.delete {
+        let file_path = plan.path(file);
+        if file_path.exists() {
+            plan.delete(&file_path)?;
+        }
+    }
+
+    // Initialize a new git repository
+    plan.git_init()?;
+
+    // Refactor each module. The main class is located in the module of the
+    // language, and the mixins may be located in another one (e.g., the Java
+    // module for Kotlin).
+    for language in language.modules() {
+        eprintln!("Refactoring {} module...", language.to_string());
+        refactor_module(&mut plan, &language, &manifest, main_class)?;
+
+        // Replace all string literals equal to the mod ID placeholder
+        for module_path in refactor::module_paths(plan.root(), &language) {
+            file::recursive_replace(
+                &mut plan,
+                &module_path,
+                &format!("\"{}\"", manifest.mod_id),
+                &format!("\"{}\"", mod_id),
+            )?;
+        }
+    }
+    let client_class = update_client_class(&mut plan, options, &manifest, template_client_class)?;
+    if options.datagen {
+        add_datagen(&mut plan, options)?;
+    }
+
+    // Move files named after the placeholders (e.g., the assets directory)
+    for rename in &manifest.rename {
+        plan.rename(
+            &plan.path(&rename.from),
+            &plan.path(rename.to.replace("{mod_id}", mod_id)),
+        )?;
+    }
+
+    eprintln!("Updating config files...");
+
+    // Update the mixins configs
+    let package = main_class[..main_class.rfind('.').unwrap()].to_string();
+    update_mixin_configs(&mut plan, &manifest, mod_id, &package)?;
+    if options.split_sources {
+        split_source_sets(&mut plan, mod_id, &package, minecraft_version)?;
+    }
+
+    // Update the mod config
+    reshape_entrypoint(&mut plan, language, main_class, entrypoint_kind)?;
+    if options.environment == Environment::Client {
+        to_client_initializer(&mut plan, language, main_class, entrypoint_kind)?;
+    }
+    update_mod_config(&mut plan, options, client_class.as_deref())?;
+    update_mixin_environment(&mut plan, options.environment)?;
+    if options.access_widener {
+        access_widener::create(&mut plan, mod_id)?;
+    }
+
+    // Update gradle.properties
+    let group = &package[..package.rfind('.').unwrap()].to_string();
+    let base_name = &package[package.rfind('.').unwrap() + 1..].to_string();
+    update_gradle_properties(&mut plan, group, base_name)?;
+    update_dependency_versions(
+        &mut plan,
+        minecraft_version,
+        &meta.resolve(minecraft_version),
+    )?;
+    update_java_version(&mut plan, minecraft_version.java_version())?;
+    check_language_plugin(plan.root(), language)?;
+    update_run_configs(&mut plan, options.environment)?;
+    update_version_fields(&mut plan, minecraft_version)?;
+
+    // Record where the mod came from
+    let lockfile = Lockfile {
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        commit,
+        options: Options {
+            template,
+            ..options.clone()
+        },
+    };
+    plan.write(&plan.path(lockfile::FILE_NAME), lockfile.contents()?)?;
+
+    Ok(plan)
+}
+
+/// Generates a mod in `path`. Nothing is left behind if it fails.
+pub fn create_mod(path: &Path, options: &Options, cache: &Cache) -> Result<(), Error> {
+    generate_mod(path, options, cache, false)
+}
+
+/// Generates a mod in a staging directory next to `path`, by executing its
+/// plan, and moves it into place once every step succeeded. The staging
+/// directory is removed on failure, unless `keep_failed` is set.
+pub fn generate_mod(
+    path: &Path,
+    options: &Options,
+    cache: &Cache,
+    keep_failed: bool,
+) -> Result<(), Error> {
+    if path.read_dir().is_ok_and(|mut dir| dir.next().is_some()) {
+        return Err(Error {
+            message: format!("Destination {} already exists", path.display()),
+        });
+    }
+
+    let staging = Staging::new(path, keep_failed)?;
+    let plan = plan_mod(&staging.path().join("work"), options, cache)?;
+    let staged = staging.path().join("mod");
+    plan.execute(&staged)?;
+    staging.finish(&staged, path)?;
+
+    // The built-in templates don't ship the wrapper jar
+    if path.join("gradlew").exists() && !path.join("gradle/wrapper/gradle-wrapper.jar").exists() {
+        println!(
+            "Run `gradle wrapper` in {} to add the Gradle wrapper jar",
+            path.display()
+        );
+    }
+    println!("Done!");
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::{Path, PathBuf};
+    use rstest::rstest;
+
+    use crate::{
+        cache::Cache,
+        code::{entrypoint::EntrypointKind, language::Language},
+        fabric::{self, Environment, Options},
+        file, lockfile,
+        plan::{Operation, Plan},
+        template::{self, Template},
+    };
+
+    /// Options for `example-mod2`, whose main class
+    /// `net.fabricmc.example2.ExampleMod2` is moved out of the template's
+    /// package
+    pub fn options(language: Language, minecraft_version: &str, template: Template) -> Options {
+        Options {
+            mod_id: "example-mod2".to_string(),
+            name: "Example Mod 2".to_string(),
+            main_class: "net.fabricmc.example2.ExampleMod2".to_string(),
+            language,
+            minecraft_version: minecraft_version.parse().unwrap(),
+            template,
+            template_ref: None,
+            meta: None,
+            entrypoint_kind: None,
+            client_class: None,
+            split_sources: false,
+            environment: Environment::Both,
+            datagen: false,
+            access_widener: false,
+        }
+    }
+
+    /// Options for `example-mod`, which keeps the names of the built-in
+    /// template
+    pub fn example_options(language: Language) -> Options {
+        Options {
+            mod_id: "example-mod".to_string(),
+            name: "Example Mod".to_string(),
+            main_class: "net.fabricmc.example.ExampleMod".to_string(),
+            ..options(language, "1.19", Template::Builtin(language))
+        }
+    }
+
+    /// Creates a mod offline, with a cache next to it
+    pub fn create_mod(path: &Path, options: &Options) {
+        let cache = Cache::at(&path.with_extension("cache"), true);
+        fabric::create_mod(path, options, &cache).unwrap();
+    }
+
+    // Returns the path to gradlew or gradlew.bat, depending on the platform
+    fn gradlew_executable(project_dir: &Path) -> PathBuf {
+        if cfg!(windows) {
+            project_dir.join("gradlew.bat")
+        } else {
+            project_dir.join("gradlew")
+        }
+    }
+
+    fn create_text_file(path: &Path, content: &str) {
+        // Create the directory if it doesn't exist
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+
+        // Create the file
+        std::fs::write(path, content).unwrap();
+    }
+
+    // Creates a minimal template with the same layout as the example mods
+    pub fn create_template(path: &Path, language: &Language) {
+        create_text_file(&path.join("LICENSE"), "CC0");
+        create_text_file(
+            &path.join("gradle.properties"),
+            "maven_group=com.example\narchives_base_name=fabric-example-mod\n",
+        );
+        create_text_file(
+            &path.join("src/main/resources/fabric.mod.json"),
+            r#"{
+  "id": "modid",
+  "name": "Example Mod",
+  "icon": "assets/modid/icon.png",
+  "entrypoints": { "main": ["net.fabricmc.example.ExampleMod"] },
+  "mixins": ["modid.mixins.json"]
+}"#,
+        );
+        create_text_file(
+            &path.join("src/main/resources/modid.mixins.json"),
+            r#"{ "package": "net.fabricmc.example.mixin", "mixins": ["ExampleMixin"] }"#,
+        );
+        create_text_file(&path.join("src/main/resources/assets/modid/icon.png"), "");
+        create_text_file(
+            &path.join("src/main/java/net/fabricmc/example/mixin/ExampleMixin.java"),
+            "package net.fabricmc.example.mixin;\n\npublic class ExampleMixin {}\n",
+        );
+        create_text_file(
+            &path
+                .join("src/main")
+                .join(language.source_dir())
+                .join("net/fabricmc/example/ExampleMod.".to_string() + language.extension()),
+            "package net.fabricmc.example\n\nclass ExampleMod { val id = \"modid\" }\n",
+        );
+    }
+
+    #[rstest]
+    #[case(Language::JAVA)]
+    #[case(Language::KOTLIN)]
+    #[case(Language::SCALA)]
+    fn test_unsupported_version(#[case] language: Language) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(fabric::create_mod(
+            &temp_dir.path().join("test"),
+            &Options {
+                mod_id: "test".to_string(),
+                name: "test".to_string(),
+                main_class: "test".to_string(),
+                ..options(language, "1.16", Template::official(&language))
+            },
+            &Cache::at(&temp_dir.path().join("cache"), false),
+        )
+        .is_err());
+    }
+
+    #[rstest]
+    #[case(Language::JAVA)]
+    #[case(Language::KOTLIN)]
+    fn test_create_mod_from_directory_template(#[case] language: Language) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_template(&template_path, &language);
+
+        let path = temp_dir
+            .path()
+            .join("test_create_mod_from_directory_template");
+        fabric::create_mod(
+            &path,
+            &options(language, "1.19", Template::Directory(template_path.clone())),
+            &Cache::at(&temp_dir.path().join("cache"), false),
+        )
+        .unwrap();
+
+        let entrypoint = path
+            .join("src/main")
+            .join(language.source_dir())
+            .join("net/fabricmc/example2/ExampleMod2.".to_string() + language.extension());
+        let contents = std::fs::read_to_string(entrypoint).unwrap();
+        assert!(contents.contains("\"example-mod2\""));
+        assert!(path
+            .join("src/main/resources/example-mod2.mixins.json")
+            .exists());
+        assert!(path.join(".git").exists());
+        assert!(!path.join("LICENSE").exists());
+
+        // The template itself is left untouched
+        assert!(template_path.join("LICENSE").exists());
+    }
+
+    #[test]
+    fn test_create_mod_from_template_with_manifest() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_text_file(
+            &template_path.join("make-fabric-mod.toml"),
+            r#"
+package = "com.acme.template"
+main_class = "TemplateMod"
+mod_id = "template"
+delete = ["NOTICE"]
+
+[[rename]]
+from = "src/main/resources/assets/template"
+to = "src/main/resources/assets/{mod_id}"
+"#,
+        );
+        create_text_file(&template_path.join("NOTICE"), "");
+        create_text_file(
+            &template_path.join("gradle.properties"),
+            "maven_group=com.acme\narchives_base_name=template-mod\nmod_url=https://com.acme/template-mod\n",
+        );
+        create_text_file(
+            &template_path.join("src/main/resources/fabric.mod.json"),
+            r#"{
+  "id": "template",
+  "entrypoints": { "main": ["com.acme.template.TemplateMod"] },
+  "mixins": ["template.mixins.json"]
+}"#,
+        );
+        create_text_file(
+            &template_path.join("src/main/resources/template.mixins.json"),
+            r#"{ "package": "com.acme.template.mixin" }"#,
+        );
+        create_text_file(
+            &template_path.join("src/main/resources/assets/template/icon.png"),
+            "",
+        );
+        create_text_file(
+            &template_path.join("src/main/java/com/acme/template/TemplateMod.java"),
+            "package com.acme.template;\n\nclass TemplateMod { String id = \"template\"; }\n",
+        );
+
+        let path = temp_dir
+            .path()
+            .join("test_create_mod_from_template_with_manifest");
+        fabric::create_mod(
+            &path,
+            &options(Language::JAVA, "1.19", Template::Directory(template_path)),
+            &Cache::at(&temp_dir.path().join("cache"), false),
+        )
+        .unwrap();
+
+        let entrypoint = path.join("src/main/java/net/fabricmc/example2/ExampleMod2.java");
+        let contents = std::fs::read_to_string(entrypoint).unwrap();
+        assert!(contents.contains("class ExampleMod2 { String id = \"example-mod2\"; }"));
+
+        assert!(path.join("src/main/resources/assets/example-mod2").exists());
+        assert!(!path.join("make-fabric-mod.toml").exists());
+        assert!(!path.join("NOTICE").exists());
+
+        let mixin_config = path.join("src/main/resources/example-mod2.mixins.json");
+        let contents = std::fs::read_to_string(mixin_config).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["package"], "net.fabricmc.example2.mixin");
+
+        let gradle_properties = std::fs::read_to_string(path.join("gradle.properties")).unwrap();
+        assert_eq!(
+            gradle_properties,
+            "maven_group=net.fabricmc\narchives_base_name=example2\nmod_url=https://com.acme/template-mod\n"
+        );
+    }
+
+    #[rstest]
+    #[case(Language::JAVA)]
+    #[case(Language::KOTLIN)]
+    fn test_create_mod_from_builtin_template(#[case] language: Language) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_create_mod_from_builtin_template");
+        fabric::create_mod(
+            &path,
+            &options(language, "1.19", Template::Builtin(language)),
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
+
+        let entrypoint = path
+            .join("src/main")
+            .join(language.source_dir())
+            .join("net/fabricmc/example2/ExampleMod2.".to_string() + language.extension());
+        let contents = std::fs::read_to_string(entrypoint).unwrap();
+        assert!(contents.contains("LoggerFactory.getLogger(\"example-mod2\")"));
+
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(
+            config["entrypoints"]["main"][0],
+            "net.fabricmc.example2.ExampleMod2"
+        );
+        assert_eq!(config["mixins"][0], "example-mod2.mixins.json");
+        assert!(path
+            .join("src/main/resources/assets/example-mod2/icon.png")
+            .exists());
+
+        let lockfile = std::fs::read_to_string(path.join(lockfile::FILE_NAME)).unwrap();
+        let lockfile: serde_json::Value = serde_json::from_str(&lockfile).unwrap();
+        assert_eq!(
+            lockfile["template"]["source"],
+            format!("builtin:{}", language.to_string())
+        );
+        assert!(lockfile["template"]["commit"].is_null());
+    }
+
+    #[rstest]
+    #[case("1.19", true)]
+    #[case("1.18", false)]
+    fn test_create_mod_falls_back_to_builtin_template(
+        #[case] minecraft_version: &str,
+        #[case] falls_back: bool,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_create_mod_falls_back_to_builtin_template");
+        // The official template isn't cached, so it's unavailable offline
+        let result = fabric::create_mod(
+            &path,
+            &options(
+                Language::JAVA,
+                minecraft_version,
+                Template::official(&Language::JAVA),
+            ),
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        );
+
+        if falls_back {
+            result.unwrap();
+            assert!(path.join("src/main/resources/fabric.mod.json").exists());
+        } else {
+            // The built-in template doesn't support the version, so the
+            // original error is reported
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("is not cached"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_create_mod_scala() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test_create_mod_scala");
+        fabric::create_mod(
+            &path,
+            &options(
+                Language::SCALA,
+                "1.19",
+                Template::official(&Language::SCALA),
+            ),
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
+
+        let entrypoint = path.join("src/main/scala/net/fabricmc/example2/ExampleMod2.scala");
+        let contents = std::fs::read_to_string(entrypoint).unwrap();
+        assert!(contents.contains("object ExampleMod2 extends ModInitializer"));
+        assert!(contents.contains("LoggerFactory.getLogger(\"example-mod2\")"));
+
+        // The mixins stay in the Java module
+        assert!(path
+            .join("src/main/java/net/fabricmc/example2/mixin/ExampleMixin.java")
+            .exists());
+
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(
+            config["entrypoints"]["main"][0],
+            serde_json::json!({
+                "adapter": "scala",
+                "value": "net.fabricmc.example2.ExampleMod2"
+            })
+        );
+
+        let build = std::fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert!(build.contains("id 'scala'"));
+        assert!(build.contains("net.fabricmc:fabric-language-scala:"));
+    }
 
-impl From<refactor::Error> for Error {
-    fn from(error: refactor::Error) -> Self {
-        Error {
-            message: error.to_string(),
-        }
+    #[rstest]
+    #[case(
+        EntrypointKind::Object,
+        "object ExampleMod2 : ModInitializer",
+        "net.fabricmc.example2.ExampleMod2"
+    )]
+    #[case(
+        EntrypointKind::Function,
+        "\nfun onInitialize() {",
+        "net.fabricmc.example2.ExampleMod2Kt::onInitialize"
+    )]
+    fn test_create_mod_kotlin_entrypoint(
+        #[case] entrypoint_kind: EntrypointKind,
+        #[case] declaration: &str,
+        #[case] value: &str,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test_create_mod_kotlin_entrypoint");
+        fabric::create_mod(
+            &path,
+            &Options {
+                entrypoint_kind: Some(entrypoint_kind),
+                ..options(
+                    Language::KOTLIN,
+                    "1.19",
+                    Template::Builtin(Language::KOTLIN),
+                )
+            },
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
+
+        let entrypoint = path.join("src/main/kotlin/net/fabricmc/example2/ExampleMod2.kt");
+        let contents = std::fs::read_to_string(entrypoint).unwrap();
+        assert!(contents.contains(declaration));
+        assert!(contents.contains("LoggerFactory.getLogger(\"example-mod2\")"));
+
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(
+            config["entrypoints"]["main"][0],
+            serde_json::json!({ "adapter": "kotlin", "value": value })
+        );
+        assert!(config["depends"]["fabric-language-kotlin"].is_string());
     }
-}
 
-impl From<serde_json::Error> for Error {
-    fn from(error: serde_json::Error) -> Self {
-        Error {
-            message: error.to_string(),
-        }
+    #[test]
+    fn test_create_mod_java_entrypoint_kind() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result = fabric::create_mod(
+            &temp_dir.path().join("test_create_mod_java_entrypoint_kind"),
+            &Options {
+                entrypoint_kind: Some(EntrypointKind::Object),
+                ..options(Language::JAVA, "1.19", Template::Builtin(Language::JAVA))
+            },
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        );
+        assert!(result.is_err());
     }
-}
 
-impl From<file::Error> for Error {
-    fn from(error: file::Error) -> Self {
-        Error {
-            message: error.to_string(),
-        }
+    #[rstest]
+    #[case(Language::JAVA, serde_json::json!("net.fabricmc.example2.client.Client"))]
+    #[case(Language::KOTLIN, serde_json::json!("net.fabricmc.example2.client.Client"))]
+    #[case(
+        Language::SCALA,
+        serde_json::json!({ "adapter": "scala", "value": "net.fabricmc.example2.client.Client" })
+    )]
+    fn test_create_mod_client_entrypoint(
+        #[case] language: Language,
+        #[case] entry: serde_json::Value,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test_create_mod_client_entrypoint");
+        fabric::create_mod(
+            &path,
+            &Options {
+                client_class: Some("net.fabricmc.example2.client.Client".to_string()),
+                split_sources: true,
+                ..options(language, "1.19", Template::Builtin(language))
+            },
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
+
+        let client = path
+            .join("src/client")
+            .join(language.source_dir())
+            .join("net/fabricmc/example2/client/Client.".to_string() + language.extension());
+        let contents = std::fs::read_to_string(client).unwrap();
+        assert!(contents.starts_with("package net.fabricmc.example2.client"));
+        assert!(contents.contains("Client"));
+        assert!(contents.contains("ClientModInitializer"));
+
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["entrypoints"]["client"][0], entry);
+        assert_eq!(
+            config["mixins"][1],
+            serde_json::json!({ "config": "example-mod2.client.mixins.json", "environment": "client" })
+        );
+
+        let mixin_config = path.join("src/client/resources/example-mod2.client.mixins.json");
+        let contents = std::fs::read_to_string(mixin_config).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["package"], "net.fabricmc.example2.mixin.client");
+
+        let build = std::fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert!(build.contains("splitEnvironmentSourceSets()"));
+        assert!(build.contains("\"example-mod2\" {"));
     }
-}
 
-fn validate_version(version: &str) -> Result<(), Error> {
-    if !version.chars().all(|c| c.is_digit(10) || c == '.') {
-        return Err(Error {
-            message: format!("Invalid version: {}", version),
-        });
+    #[test]
+    fn test_write_class() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut plan = Plan::new(temp_dir.path());
+        let source = "package net.fabricmc.example;\n\n\
+            // ExampleModClient in net.fabricmc.example\n\
+            class ExampleModClient { String name = \"ExampleModClient\"; ExampleModClientHelper helper; }\n";
+        fabric::write_class(
+            &mut plan,
+            "src/main",
+            &Language::JAVA,
+            source,
+            "net.fabricmc.example.ExampleModClient",
+            "com.example.client.Client",
+        )
+        .unwrap();
+
+        let contents = std::fs::read_to_string(
+            temp_dir
+                .path()
+                .join("src/main/java/com/example/client/Client.java"),
+        )
+        .unwrap();
+        assert_eq!(
+            contents,
+            "package com.example.client;\n\n\
+            // ExampleModClient in net.fabricmc.example\n\
+            class Client { String name = \"ExampleModClient\"; ExampleModClientHelper helper; }\n"
+        );
     }
 
-    let parts = version.split('.');
-    if parts.count() != 2 {
-        return Err(Error {
-            message: format!("Invalid version: {}. Expected 2 parts (e.g. 1.19)", version),
-        });
+    #[rstest]
+    #[case("1.17.1", "JAVA_16")]
+    #[case("1.19", "JAVA_17")]
+    #[case("1.20.5", "JAVA_21")]
+    fn test_split_source_sets_java_version(
+        #[case] minecraft_version: &str,
+        #[case] compatibility_level: &str,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        template::builtin::write(temp_dir.path(), &Language::JAVA).unwrap();
+        let mut plan = Plan::new(temp_dir.path());
+        fabric::split_source_sets(
+            &mut plan,
+            "example-mod",
+            "net.fabricmc.example",
+            &minecraft_version.parse().unwrap(),
+        )
+        .unwrap();
+
+        let mixin_config = temp_dir
+            .path()
+            .join("src/client/resources/example-mod.client.mixins.json");
+        let contents = std::fs::read_to_string(mixin_config).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["compatibilityLevel"], compatibility_level);
     }
 
-    return Ok(());
-}
+    #[test]
+    fn test_create_mod_renames_client_entrypoint() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_template(&template_path, &Language::JAVA);
+        create_text_file(
+            &template_path.join("src/main/resources/fabric.mod.json"),
+            r#"{
+  "id": "modid",
+  "entrypoints": {
+    "main": ["net.fabricmc.example.ExampleMod"],
+    "client": ["net.fabricmc.example.ExampleModClient"]
+  },
+  "mixins": ["modid.mixins.json"]
+}"#,
+        );
+        create_text_file(
+            &template_path.join("src/client/java/net/fabricmc/example/ExampleModClient.java"),
+            "package net.fabricmc.example;\n\nclass ExampleModClient {}\n",
+        );
 
-fn update_mod_config(path: &Path, mod_id: &str, main_class: &str, name: &str) -> Result<(), Error> {
-    let config_path = path.join("src/main/resources/fabric.mod.json");
-    let mut config: serde_json::Value =
-        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+        let path = temp_dir
+            .path()
+            .join("test_create_mod_renames_client_entrypoint");
+        fabric::create_mod(
+            &path,
+            &options(Language::JAVA, "1.19", Template::Directory(template_path)),
+            &Cache::at(&temp_dir.path().join("cache"), false),
+        )
+        .unwrap();
 
-    config["id"] = serde_json::Value::String(mod_id.to_string());
-    config["name"] = serde_json::Value::String(name.to_string());
-    config["description"] = serde_json::Value::String("".to_string());
-    config["icon"] = serde_json::Value::String(format!("assets/{}/icon.png", mod_id));
-    config["entrypoints"]["main"][0] = serde_json::Value::String(main_class.to_string());
-    config["mixins"][0] = serde_json::Value::String(format!("{}.mixins.json", mod_id));
+        let client = path.join("src/client/java/net/fabricmc/example2/ExampleMod2Client.java");
+        let contents = std::fs::read_to_string(client).unwrap();
+        assert_eq!(
+            contents,
+            "package net.fabricmc.example2;\n\nclass ExampleMod2Client {}\n"
+        );
+        assert!(!path.join("src/client/java/net/fabricmc/example").exists());
 
-    std::fs::write(config_path, serde_json::to_string_pretty(&config)?)?;
-    Ok(())
-}
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(
+            config["entrypoints"]["client"][0],
+            "net.fabricmc.example2.ExampleMod2Client"
+        );
+    }
 
-fn update_mixin_config(path: &Path, mod_id: &str, mixin_package: &str) -> Result<(), Error> {
-    let config_path = path.join(format!("src/main/resources/{}.mixins.json", mod_id));
-    let mut config: serde_json::Value =
-        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
-    config["package"] = serde_json::Value::String(mixin_package.to_string());
-    std::fs::write(config_path, serde_json::to_string_pretty(&config)?)?;
-    Ok(())
-}
+    #[rstest]
+    #[case(Language::JAVA)]
+    #[case(Language::KOTLIN)]
+    #[case(Language::SCALA)]
+    fn test_create_mod_client_environment(#[case] language: Language) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test_create_mod_client_environment");
+        fabric::create_mod(
+            &path,
+            &Options {
+                environment: Environment::Client,
+                ..options(language, "1.19", Template::Builtin(language))
+            },
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
 
-fn update_gradle_properties(path: &Path, group: &str, base_name: &str) -> Result<(), Error> {
-    let config_path = path.join("gradle.properties");
-    let mut config = std::fs::read_to_string(&config_path)?;
-    config = config.replace("com.example", group);
-    config = config.replace("fabric-example-mod", base_name);
-    std::fs::write(config_path, config)?;
-    Ok(())
-}
+        let main = path
+            .join("src/main")
+            .join(language.source_dir())
+            .join("net/fabricmc/example2/ExampleMod2.".to_string() + language.extension());
+        let contents = std::fs::read_to_string(main).unwrap();
+        assert!(contents.contains("ClientModInitializer"));
+        assert!(contents.contains("onInitializeClient"));
 
-fn refactor_module(path: &Path, language: &Language, main_class: &str) -> Result<(), Error> {
-    // Rename the package
-    let old_package = "net.fabricmc.example";
-    let new_package = main_class[..main_class.rfind('.').unwrap()].to_string();
-    refactor::rename_package(path, language, &old_package, &new_package)?;
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["environment"], "client");
+        assert!(config["entrypoints"]["main"].is_null());
+        assert!(!config["entrypoints"]["client"].is_null());
 
-    // Rename the main class (if contained in this module)
-    let main_class_exists = path
-        .join("src/main")
-        .join(language.to_string())
-        .join(new_package.replace('.', "/"))
-        .join(format!("ExampleMod.{}", language.extension()))
-        .exists();
-
-    if main_class_exists {
-        let old_class = format!("{}.ExampleMod", &new_package);
-        let new_class = main_class;
-        refactor::rename_class(path, language, &old_class, &new_class)?;
+        let build = std::fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert!(build.contains("server {\n\t\t\tideConfigGenerated false"));
     }
 
-    Ok(())
-}
+    #[test]
+    fn test_create_mod_server_environment() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test_create_mod_server_environment");
+        let mut options = Options {
+            client_class: Some("net.fabricmc.example2.client.Client".to_string()),
+            environment: Environment::Server,
+            ..options(Language::JAVA, "1.19", Template::Builtin(Language::JAVA))
+        };
+        let cache = Cache::at(&temp_dir.path().join("cache"), true);
+        assert!(fabric::create_mod(&path, &options, &cache).is_err());
 
-pub fn create_mod(
-    path: &Path,
-    mod_id: &str,
-    minecraft_version: &str,
-    language: &Language,
-    main_class: &str,
-    name: &str,
-) -> Result<(), Error> {
-    validate_version(minecraft_version)?;
+        options.client_class = None;
+        fabric::create_mod(&path, &options, &cache).unwrap();
 
-    // Clone the Kotlin example mod
-    let template_url = match language {
-        Language::Kotlin => "https://github.com/clabe45/fabric-example-mod-kotlin",
-        Language::Java => "https://github.com/FabricMC/fabric-example-mod",
-    };
-    println!("Cloning {}...", template_url);
-    let global = git::Context::new(&None)?;
-    global.git(&["clone", "--depth", "1", "--branch", minecraft_version, template_url, path.to_str().unwrap()])
-        .map_err(|e| match e.kind() {
-            // If git is installed but the command failed, it's probably because
-            // the version branch doesn't exist
-            git::ErrorKind::GitFailed => Error {
-                message: format!("Unsupported Minecraft version: {}", minecraft_version),
+        let resources = path.join("src/main/resources");
+        let contents = std::fs::read_to_string(resources.join("fabric.mod.json")).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["environment"], "server");
+        assert!(config["entrypoints"]["client"].is_null());
+        assert_eq!(
+            config["entrypoints"]["main"][0],
+            "net.fabricmc.example2.ExampleMod2"
+        );
+
+        let contents = std::fs::read_to_string(resources.join("example-mod2.mixins.json")).unwrap();
+        let mixins: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert!(mixins.get("client").is_none());
+    }
+
+    #[rstest]
+    #[case(Language::JAVA, serde_json::json!("net.fabricmc.example2.ExampleMod2DataGenerator"))]
+    #[case(Language::KOTLIN, serde_json::json!("net.fabricmc.example2.ExampleMod2DataGenerator"))]
+    #[case(
+        Language::SCALA,
+        serde_json::json!({ "adapter": "scala", "value": "net.fabricmc.example2.ExampleMod2DataGenerator" })
+    )]
+    fn test_create_mod_datagen(#[case] language: Language, #[case] entry: serde_json::Value) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test_create_mod_datagen");
+        fabric::create_mod(
+            &path,
+            &Options {
+                datagen: true,
+                ..options(language, "1.19", Template::Builtin(language))
             },
-            _ => e.into(),
-        })?;
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
+
+        let datagen = path.join("src/main").join(language.source_dir()).join(
+            "net/fabricmc/example2/ExampleMod2DataGenerator.".to_string() + language.extension(),
+        );
+        let contents = std::fs::read_to_string(datagen).unwrap();
+        assert!(contents.starts_with("package net.fabricmc.example2"));
+        assert!(contents.contains("ExampleMod2DataGenerator"));
+        assert!(contents.contains("DataGeneratorEntrypoint"));
 
-    println!("Re-initializing git repository...");
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["entrypoints"]["fabric-datagen"][0], entry);
 
-    // Remove the license file if present
-    let license_path = path.join("LICENSE");
-    if license_path.exists() {
-        std::fs::remove_file(license_path)?;
+        let build = std::fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert!(build.contains("fabricApi {\n\tconfigureDataGeneration()\n}"));
+        assert!(path.join("src/main/generated").is_dir());
     }
 
-    // Remove the .git directory
-    let git_dir = path.join(".git");
-    std::fs::remove_dir_all(git_dir)?;
+    #[test]
+    fn test_plan_mod() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test_plan_mod");
+        std::fs::create_dir(&path).unwrap();
+        let options = options(Language::JAVA, "1.19", Template::Builtin(Language::JAVA));
+        let plan = fabric::plan_mod(
+            &temp_dir.path().join("work"),
+            &options,
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
+
+        // Planning doesn't touch the target directory
+        assert!(path.read_dir().unwrap().next().is_none());
 
-    // Re-initialize the git repository
-    let repo = git::Context::new(&Some(path))?;
-    repo.git(&["init"])?;
+        let operations: Vec<String> = plan
+            .operation_files()
+            .map(|(op, _)| op.to_string())
+            .collect();
+        assert_eq!(operations[..2], ["clone builtin:java", "git init"]);
+        assert!(operations.contains(
+            &"rename src/main/java/net/fabricmc/example -> src/main/java/net/fabricmc/example2"
+                .to_string()
+        ));
+        assert!(operations.contains(&"edit src/main/resources/fabric.mod.json".to_string()));
 
-    // Refactor each module. If --kotlin is specified, refactor both the Java
-    // and Kotlin modules. The mixins are located in the Java module, and
-    // everything else is located in the Kotlin module. If --kotlin is not
-    // specified, only refactor the Java module.
-    let languages = match language {
-        Language::Java => vec![Language::Java],
-        Language::Kotlin => vec![Language::Kotlin, Language::Java],
-    };
-    for language in languages {
-        println!("Refactoring {} module...", language.to_string());
-        refactor_module(path, &language, main_class)?;
+        // Executing the plan creates the same files as in the working directory
+        plan.execute(&path).unwrap();
+        let files = file::list_files(&path).unwrap();
+        assert_eq!(files, file::list_files(plan.root()).unwrap());
+        for file in &files {
+            assert_eq!(
+                std::fs::read(path.join(file)).unwrap(),
+                std::fs::read(plan.path(file)).unwrap()
+            );
+        }
 
-        // Replace all string literals equal to "modid" with the mod ID
-        let module_root_path = path.join("src/main").join(language.to_string());
-        file::recursive_replace(&module_root_path, "\"modid\"", &format!("\"{}\"", mod_id))?;
+        // Every file created is shown in the plan
+        let mut planned = Vec::new();
+        for (operation, operation_files) in plan.operation_files() {
+            planned.extend_from_slice(operation_files);
+            match operation {
+                Operation::RewriteFile { path, .. } | Operation::EditJson { path, .. } => {
+                    planned.push(path.clone())
+                }
+                Operation::Rename { to, .. } => planned.push(to.clone()),
+                _ => (),
+            }
+        }
+        for file in &files {
+            assert!(planned.contains(file), "{} isn't planned", file.display());
+        }
+        assert!(path.join(".git").is_dir());
+        assert!(path
+            .join("src/main/java/net/fabricmc/example2/ExampleMod2.java")
+            .exists());
     }
 
-    // Move the assets directory to the correct location
-    let old_assets_path = path.join("src/main/resources/assets/modid");
-    let new_assets_path = path.join(format!("src/main/resources/assets/{}", mod_id));
-    std::fs::rename(old_assets_path, new_assets_path)?;
+    #[test]
+    fn test_create_mod_pins_dependency_versions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_create_mod_pins_dependency_versions");
+        fabric::create_mod(
+            &path,
+            &options(Language::JAVA, "1.19.2", Template::Builtin(Language::JAVA)),
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
 
-    println!("Updating config files...");
+        let properties = std::fs::read_to_string(path.join("gradle.properties")).unwrap();
+        assert!(properties.contains("minecraft_version=1.19.2\n"));
+        assert!(properties.contains("yarn_mappings=1.19.2+build.28\n"));
+        assert!(properties.contains("fabric_version=0.77.0+1.19.2"));
 
-    // Update the mixins config
-    std::fs::rename(
-        path.join("src/main/resources/modid.mixins.json"),
-        path.join(format!("src/main/resources/{}.mixins.json", mod_id)),
-    )?;
-    let mixin_package = format!(
-        "{}.mixin",
-        main_class[..main_class.rfind('.').unwrap()].to_string()
-    );
-    update_mixin_config(path, mod_id, &mixin_package)?;
+        let build = std::fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert!(build.contains("id 'fabric-loom' version '1.2-SNAPSHOT'"));
 
-    // Update the mod config
-    update_mod_config(path, mod_id, main_class, name)?;
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["depends"]["minecraft"], "~1.19.2");
+    }
 
-    // Update gradle.properties
-    let package = main_class[..main_class.rfind('.').unwrap()].to_string();
-    let group = &package[..package.rfind('.').unwrap()].to_string();
-    let base_name = &package[package.rfind('.').unwrap() + 1..].to_string();
-    update_gradle_properties(path, &group, &base_name)?;
+    #[rstest]
+    #[case("1.16.5", 8, "1_8")]
+    #[case("1.17.1", 16, "16")]
+    #[case("1.20.5", 21, "21")]
+    fn test_create_mod_sets_java_version(
+        #[case] minecraft_version: &str,
+        #[case] java: u32,
+        #[case] java_version: &str,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        template::builtin::write(&template_path, &Language::JAVA).unwrap();
 
-    println!("Done!");
-    Ok(())
-}
+        let path = temp_dir.path().join("test_create_mod_sets_java_version");
+        fabric::create_mod(
+            &path,
+            &options(
+                Language::JAVA,
+                minecraft_version,
+                Template::Directory(template_path),
+            ),
+            &Cache::at(&temp_dir.path().join("cache"), true),
+        )
+        .unwrap();
 
-#[cfg(test)]
-mod tests {
-    use std::path::{Path, PathBuf};
-    use rstest::rstest;
+        let build = std::fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert!(build.contains(&format!("it.options.release = {}\n", java)));
+        assert!(build.contains(&format!("JavaVersion.VERSION_{}\n", java_version)));
 
-    use crate::{code::language::Language, fabric};
+        let resources = path.join("src/main/resources");
+        let contents = std::fs::read_to_string(resources.join("example-mod2.mixins.json")).unwrap();
+        let mixins: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(mixins["compatibilityLevel"], format!("JAVA_{}", java));
 
-    // Returns the path to gradlew or gradlew.bat, depending on the platform
-    fn gradlew_executable(project_dir: &Path) -> PathBuf {
-        let gradlew = if cfg!(windows) {
-            project_dir.join("gradlew.bat")
-        } else {
-            project_dir.join("gradlew")
-        };
-        gradlew
+        let contents = std::fs::read_to_string(resources.join("fabric.mod.json")).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["depends"]["java"], format!(">={}", java));
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn test_generate_mod_cleans_up_on_failure(#[case] keep_failed: bool) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        template::builtin::write(&template_path, &Language::JAVA).unwrap();
+        // The assets directory can't be renamed
+        std::fs::remove_dir_all(template_path.join("src/main/resources/assets")).unwrap();
+
+        let mods = temp_dir.path().join("mods");
+        let path = mods.join("example-mod2");
+        std::fs::create_dir_all(&path).unwrap();
+        let mut options = options(Language::JAVA, "1.19", Template::Directory(template_path));
+        let cache = Cache::at(&temp_dir.path().join("cache"), true);
+        assert!(fabric::generate_mod(&path, &options, &cache, keep_failed).is_err());
+
+        // The empty target is left as it was
+        assert!(path.read_dir().unwrap().next().is_none());
+        let staging: Vec<_> = std::fs::read_dir(&mods).unwrap().collect();
+        assert_eq!(staging.len(), 1 + keep_failed as usize);
+
+        // Nothing is in the way of another attempt
+        options.template = Template::Builtin(Language::JAVA);
+        fabric::generate_mod(&path, &options, &cache, keep_failed).unwrap();
+        assert!(path.join("src/main/resources/assets/example-mod2").is_dir());
+        assert!(fabric::create_mod(&path, &options, &cache).is_err());
+    }
+
+    #[test]
+    fn test_create_mod_from_git_template() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_template(&template_path, &Language::JAVA);
+        template::tests::commit_all(&template_path, "1.19");
+
+        let path = temp_dir.path().join("test_create_mod_from_git_template");
+        fabric::create_mod(
+            &path,
+            &options(
+                Language::JAVA,
+                "1.19",
+                Template::parse(template_path.to_str().unwrap()).unwrap(),
+            ),
+            &Cache::at(&temp_dir.path().join("cache"), false),
+        )
+        .unwrap();
+
+        let mod_json = path.join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["id"], "example-mod2");
+
+        let gradle_properties = std::fs::read_to_string(path.join("gradle.properties")).unwrap();
+        assert!(gradle_properties.contains("maven_group=net.fabricmc"));
+        assert!(gradle_properties.contains("archives_base_name=example2"));
     }
 
     #[test]
-    fn test_validate_version() {
-        assert!(fabric::validate_version("1.17").is_ok());
-        assert!(fabric::validate_version("1").is_err());
-        assert!(fabric::validate_version("1.17.1").is_err());
+    fn test_create_mod_from_git_template_at_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_template(&template_path, &Language::JAVA);
+        template::tests::commit_all(&template_path, "main");
+        let output = std::process::Command::new("git")
+            .current_dir(&template_path)
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .unwrap();
+        let commit = String::from_utf8(output.stdout).unwrap().trim().to_string();
+
+        let path = temp_dir
+            .path()
+            .join("test_create_mod_from_git_template_at_commit");
+        let mut options = Options {
+            template_ref: Some(commit.clone()),
+            ..options(
+                Language::JAVA,
+                "1.19",
+                Template::parse(template_path.to_str().unwrap()).unwrap(),
+            )
+        };
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+        fabric::create_mod(&path, &options, &cache).unwrap();
+
+        assert!(path
+            .join("src/main/java/net/fabricmc/example2/ExampleMod2.java")
+            .exists());
+
+        let lockfile = std::fs::read_to_string(path.join(lockfile::FILE_NAME)).unwrap();
+        let lockfile: serde_json::Value = serde_json::from_str(&lockfile).unwrap();
+        assert_eq!(lockfile["template"]["commit"], commit);
+        assert_eq!(lockfile["tool_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(lockfile["mod"]["id"], "example-mod2");
+
+        options.template_ref = Some("missing".to_string());
+        let error =
+            fabric::create_mod(&temp_dir.path().join("missing"), &options, &cache).unwrap_err();
+        assert_eq!(error.to_string(), "Template ref not found: missing");
     }
 
-    #[rstest]
-    #[case(Language::Java)]
-    #[case(Language::Kotlin)]
-    fn test_unsupported_version(#[case] language: Language) {
-        assert!(fabric::create_mod(
-            &std::path::Path::new("test"),
-            "test",
-            "1.16",
-            &language,
-            "test",
-            "test"
+    #[test]
+    fn test_create_mod_from_git_template_unsupported_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_template(&template_path, &Language::JAVA);
+        template::tests::commit_all(&template_path, "1.19");
+
+        let path = temp_dir.path().join("test_unsupported_version");
+        let error = fabric::create_mod(
+            &path,
+            &options(
+                Language::JAVA,
+                "1.18",
+                Template::parse(template_path.to_str().unwrap()).unwrap(),
+            ),
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
-        .is_err());
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Unsupported Minecraft version: 1.18 (nearest supported version: 1.19)"
+        );
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
-    fn test_create_mod_creates_git_repo(#[case] language: Language, #[case] minecraft_version: &str) {
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
+    fn test_create_mod_creates_git_repo(
+        #[case] language: Language,
+        #[case] minecraft_version: &str,
+    ) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir.path().join("test_create_mod_creates_git_repo");
         fabric::create_mod(
             &path,
-            "example-mod",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example.ExampleMod",
-            "Example Mod",
+            &Options {
+                minecraft_version: minecraft_version.parse().unwrap(),
+                template: Template::official(&language),
+                ..example_options(language)
+            },
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
@@ -283,20 +1978,24 @@ mod tests {
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
-    fn test_create_mod_excludes_license(#[case] language: Language, #[case] minecraft_version: &str) {
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
+    fn test_create_mod_excludes_license(
+        #[case] language: Language,
+        #[case] minecraft_version: &str,
+    ) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir.path().join("test_create_mod_creates_git_repo");
         fabric::create_mod(
             &path,
-            "example-mod",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example.ExampleMod",
-            "Example Mod",
+            &Options {
+                minecraft_version: minecraft_version.parse().unwrap(),
+                template: Template::official(&language),
+                ..example_options(language)
+            },
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
@@ -305,46 +2004,46 @@ mod tests {
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
-    fn test_create_mod_moves_entrypoint(#[case] language: Language, #[case] minecraft_version: &str) {
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
+    fn test_create_mod_moves_entrypoint(
+        #[case] language: Language,
+        #[case] minecraft_version: &str,
+    ) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir.path().join("test_create_mod_moves_entrypoint");
         fabric::create_mod(
             &path,
-            "example-mod2",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example2.ExampleMod2",
-            "Example Mod 2",
+            &options(language, minecraft_version, Template::official(&language)),
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
         let entrypoint = path
             .join("src/main")
-            .join(language.to_string())
+            .join(language.source_dir())
             .join("net/fabricmc/example2/ExampleMod2.".to_string() + language.extension());
 
         assert!(entrypoint.exists());
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
     fn test_create_mod_moves_assets(#[case] language: Language, #[case] minecraft_version: &str) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir.path().join("test_create_mod_moves_assets");
         fabric::create_mod(
             &path,
-            "example-mod2",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example3.ExampleMod2",
-            "Example Mod 2",
+            &Options {
+                main_class: "net.fabricmc.example3.ExampleMod2".to_string(),
+                ..options(language, minecraft_version, Template::official(&language))
+            },
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
@@ -353,20 +2052,23 @@ mod tests {
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
-    fn test_create_mod_renames_mixin_config(#[case] language: Language, #[case] minecraft_version: &str) {
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
+    fn test_create_mod_renames_mixin_config(
+        #[case] language: Language,
+        #[case] minecraft_version: &str,
+    ) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir.path().join("test_create_mod_renames_mixin_config");
         fabric::create_mod(
             &path,
-            "example-mod2",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example3.ExampleMod2",
-            "Example Mod 2",
+            &Options {
+                main_class: "net.fabricmc.example3.ExampleMod2".to_string(),
+                ..options(language, minecraft_version, Template::official(&language))
+            },
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
@@ -375,20 +2077,20 @@ mod tests {
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
-    fn test_create_mod_updates_mixin_config(#[case] language: Language, #[case] minecraft_version: &str) {
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
+    fn test_create_mod_updates_mixin_config(
+        #[case] language: Language,
+        #[case] minecraft_version: &str,
+    ) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir.path().join("test_create_mod_updates_mixin_config");
         fabric::create_mod(
             &path,
-            "example-mod2",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example2.ExampleMod2",
-            "Example Mod 2",
+            &options(language, minecraft_version, Template::official(&language)),
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
@@ -402,20 +2104,20 @@ mod tests {
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
-    fn test_create_mod_updates_mod_config(#[case] language: Language, #[case] minecraft_version: &str) {
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
+    fn test_create_mod_updates_mod_config(
+        #[case] language: Language,
+        #[case] minecraft_version: &str,
+    ) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir.path().join("test_create_mod_updates_mod_id");
         fabric::create_mod(
             &path,
-            "example-mod2",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example2.ExampleMod2",
-            "Example Mod 2",
+            &options(language, minecraft_version, Template::official(&language)),
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
@@ -434,22 +2136,22 @@ mod tests {
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
-    fn test_create_mod_updates_gradle_properties(#[case] language: Language, #[case] minecraft_version: &str) {
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
+    fn test_create_mod_updates_gradle_properties(
+        #[case] language: Language,
+        #[case] minecraft_version: &str,
+    ) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir
             .path()
             .join("test_create_mod_updates_gradle_properties");
         fabric::create_mod(
             &path,
-            "example-mod2",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example2.ExampleMod2",
-            "Example Mod 2",
+            &options(language, minecraft_version, Template::official(&language)),
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
@@ -460,20 +2162,17 @@ mod tests {
     }
 
     #[rstest]
-    #[case(Language::Java, "1.18")]
-    #[case(Language::Kotlin, "1.18")]
-    #[case(Language::Java, "1.19")]
-    #[case(Language::Kotlin, "1.19")]
+    #[case(Language::JAVA, "1.18")]
+    #[case(Language::KOTLIN, "1.18")]
+    #[case(Language::JAVA, "1.19")]
+    #[case(Language::KOTLIN, "1.19")]
     fn test_create_mod_can_compile(#[case] language: Language, #[case] minecraft_version: &str) {
         let temp_dir = tempfile::tempdir().unwrap();
         let path = temp_dir.path().join("test_create_mod_can_compile");
         fabric::create_mod(
             &path,
-            "example-mod2",
-            minecraft_version,
-            &language,
-            "net.fabricmc.example2.ExampleMod2",
-            "Example Mod 2",
+            &options(language, minecraft_version, Template::official(&language)),
+            &Cache::at(&temp_dir.path().join("cache"), false),
         )
         .unwrap();
 
@@ -1,4 +1,9 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    code::language::Language,
+    plan::{self, Plan},
+};
 
 #[derive(Debug)]
 pub struct Error {
@@ -19,6 +24,14 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<plan::Error> for Error {
+    fn from(error: plan::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
 fn is_dir_empty(path: &Path) -> Result<bool, Error> {
     std::fs::read_dir(path)?
         .next()
@@ -40,40 +53,85 @@ pub fn remove_empty_parent_dirs(path: &Path) -> Result<(), Error> {
 
 fn is_text_file(path: &Path) -> bool {
     let extension = path.extension().unwrap_or_default();
-    match extension.to_str() {
-        Some("gradle") => true,
-        Some("java") => true,
-        Some("json") => true,
-        Some("kt") => true,
-        Some("properties") => true,
-        _ => false,
-    }
+    matches!(
+        extension.to_str(),
+        Some("gradle") | Some("json") | Some("properties") | Some("accesswidener")
+    ) || Language::all().any(|language| extension == language.extension())
 }
 
-fn replace_in_file(path: &Path, from: &str, to: &str) -> Result<(), Error> {
+fn replace_in_file(plan: &mut Plan, path: &Path, from: &str, to: &str) -> Result<(), Error> {
     if !is_text_file(path) {
         return Ok(());
     }
 
-    let mut file = std::fs::read_to_string(path)?;
-    file = file.replace(from, to);
-    std::fs::write(path, file)?;
+    let file = std::fs::read_to_string(path)?;
+    let replaced = file.replace(from, to);
+    if replaced != file {
+        plan.write(path, replaced)?;
+    }
     Ok(())
 }
 
-pub fn recursive_replace(path: &Path, old: &str, new: &str) -> Result<(), Error> {
+pub fn recursive_replace(plan: &mut Plan, path: &Path, old: &str, new: &str) -> Result<(), Error> {
     for entry in std::fs::read_dir(path)? {
         let entry = entry?;
         let path = entry.path();
         if path.is_dir() {
-            recursive_replace(&path, old, new)?;
+            recursive_replace(plan, &path, old, new)?;
+        } else {
+            replace_in_file(plan, &path, old, new)?;
+        }
+    }
+    Ok(())
+}
+
+/// Recursively copies the contents of `from` into `to`, skipping any `.git`
+/// directories
+pub fn copy_dir(from: &Path, to: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
         } else {
-            replace_in_file(&path, old, new)?;
+            std::fs::copy(&path, &target)?;
         }
     }
     Ok(())
 }
 
+/// Lists the files under `root` relative to it, skipping any `.git`
+/// directories
+pub fn list_files(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    fn visit(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_name() == ".git" {
+                continue;
+            }
+
+            let path = entry.path();
+            if path.is_dir() {
+                visit(root, &path, files)?;
+            } else {
+                files.push(path.strip_prefix(root).unwrap().to_path_buf());
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    visit(root, root, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs, io::Write, path::Path};
@@ -102,6 +160,14 @@ mod tests {
         file.write_all(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
     }
 
+    #[test]
+    fn test_is_text_file() {
+        assert!(is_text_file(Path::new("fabric.mod.json")));
+        assert!(is_text_file(Path::new("modid.accesswidener")));
+        assert!(is_text_file(Path::new("ExampleMod.kt")));
+        assert!(!is_text_file(Path::new("icon.png")));
+    }
+
     #[test]
     fn test_recursive_replace() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -109,9 +175,38 @@ mod tests {
         create_text_file(&test_file, "old old old");
         create_binary_file(&temp_dir.path().join("test_file.bin"));
 
-        recursive_replace(&temp_dir.path(), "old", "new").unwrap();
+        let mut plan = Plan::new(temp_dir.path());
+        recursive_replace(&mut plan, temp_dir.path(), "old", "new").unwrap();
 
         let content = fs::read_to_string(&test_file).unwrap();
         assert_eq!(content, "new new new");
     }
+
+    #[test]
+    fn test_copy_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let from = temp_dir.path().join("from");
+        let to = temp_dir.path().join("to");
+        create_text_file(&from.join("a/b.properties"), "b");
+        create_text_file(&from.join(".git/HEAD"), "ref: refs/heads/main");
+
+        copy_dir(&from, &to).unwrap();
+
+        let content = fs::read_to_string(to.join("a/b.properties")).unwrap();
+        assert_eq!(content, "b");
+        assert!(!to.join(".git").exists());
+    }
+
+    #[test]
+    fn test_list_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_text_file(&temp_dir.path().join("b.json"), "{}");
+        create_text_file(&temp_dir.path().join("a/c.properties"), "c");
+        create_text_file(&temp_dir.path().join(".git/HEAD"), "ref: refs/heads/main");
+
+        assert_eq!(
+            list_files(temp_dir.path()).unwrap(),
+            vec![PathBuf::from("a/c.properties"), PathBuf::from("b.json")]
+        );
+    }
 }
@@ -83,6 +83,93 @@ impl Context {
 
         Ok(stdout)
     }
+
+    /// Whether the working tree has no uncommitted or untracked changes. Fails
+    /// outside of a git repository.
+    pub fn is_clean(&self) -> Result<bool, Error> {
+        Ok(self.git(&["status", "--porcelain"])?.trim().is_empty())
+    }
+
+    /// Checks out a single commit of `url` in the context's directory, which
+    /// must already exist. Unlike `clone --branch`, this works with commit
+    /// SHAs.
+    pub fn checkout_commit(&self, url: &str, commit: &str) -> Result<(), Error> {
+        self.git(&["init", "--quiet"])?;
+        self.git(&["remote", "add", "origin", url])?;
+
+        if self
+            .git(&["fetch", "--quiet", "--depth", "1", "origin", commit])
+            .is_ok()
+        {
+            self.git(&["checkout", "--quiet", "FETCH_HEAD"])?;
+        } else {
+            // Abbreviated SHAs can't be fetched directly, so fetch everything
+            // and let git resolve it
+            self.git(&["fetch", "--quiet", "--tags", "origin"])?;
+            self.git(&["checkout", "--quiet", commit])?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether `reference` is a branch or tag of the repository
+    pub fn has_ref(&self, reference: &str) -> bool {
+        ["refs/heads/", "refs/tags/"].iter().any(|prefix| {
+            self.git(&[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &(prefix.to_string() + reference),
+            ])
+            .is_ok()
+        })
+    }
+
+    /// Whether `reference` is a branch or tag of the remote `url`
+    pub fn remote_has_ref(&self, url: &str, reference: &str) -> Result<bool, Error> {
+        let branch = format!("refs/heads/{}", reference);
+        let tag = format!("refs/tags/{}", reference);
+        Ok(!self
+            .git(&["ls-remote", url, &branch, &tag])?
+            .trim()
+            .is_empty())
+    }
+
+    /// Merges the changes from `base` to `other` into `current` in place,
+    /// writing conflict markers labelled with `labels` where they overlap.
+    /// Returns whether there were any conflicts.
+    pub fn merge_file(
+        &self,
+        current: &Path,
+        base: &Path,
+        other: &Path,
+        labels: [&str; 3],
+    ) -> Result<bool, Error> {
+        let mut command = Command::new("git");
+        command.current_dir(&self.path);
+        command.arg("merge-file");
+        for label in labels {
+            command.args(["-L", label]);
+        }
+        command.arg(current).arg(base).arg(other);
+
+        // The exit code is the number of conflicts, or negative on failure
+        let output = command.output()?;
+        match output.status.code() {
+            Some(0) => Ok(false),
+            Some(code) if (1..128).contains(&code) => Ok(true),
+            _ => Err(Error {
+                message: String::from_utf8(output.stderr)?,
+                kind: ErrorKind::GitFailed,
+            }),
+        }
+    }
+}
+
+/// Whether a ref looks like a (possibly abbreviated) commit SHA. Branches and
+/// tags can look like one too (e.g., `cafe123`), so check for those first.
+pub fn is_commit_sha(reference: &str) -> bool {
+    (7..=40).contains(&reference.len()) && reference.chars().all(|c| c.is_ascii_hexdigit())
 }
 
 #[cfg(test)]
@@ -95,4 +182,110 @@ mod tests {
         let output = context.git(&["--version"]).unwrap();
         assert!(output.starts_with("git version"));
     }
+
+    #[test]
+    fn test_is_commit_sha() {
+        assert!(is_commit_sha("d9831a9"));
+        assert!(is_commit_sha("d9831a95c9af78987a58cfd0bee1158fc5240be0"));
+        assert!(!is_commit_sha("1.19"));
+        assert!(!is_commit_sha("abc"));
+        assert!(!is_commit_sha("main"));
+    }
+
+    #[test]
+    fn test_has_ref() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path();
+        std::fs::write(path.join("file.txt"), "content").unwrap();
+        crate::template::tests::commit_all(path, "deadbeef");
+        let context = Context::new(&Some(path)).unwrap();
+        context.git(&["tag", "v1"]).unwrap();
+
+        assert!(context.has_ref("deadbeef"));
+        assert!(context.has_ref("v1"));
+        assert!(!context.has_ref("cafe123"));
+
+        let url = format!("file://{}", path.canonicalize().unwrap().display());
+        let global = Context::new(&None).unwrap();
+        assert!(global.remote_has_ref(&url, "deadbeef").unwrap());
+        assert!(global.remote_has_ref(&url, "v1").unwrap());
+        assert!(!global.remote_has_ref(&url, "cafe123").unwrap());
+    }
+
+    #[test]
+    fn test_checkout_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let origin_path = temp_dir.path().join("origin");
+        std::fs::create_dir(&origin_path).unwrap();
+        let origin = Context::new(&Some(&origin_path)).unwrap();
+        origin.git(&["init", "--quiet"]).unwrap();
+
+        let mut commits = Vec::new();
+        for content in ["first", "second"] {
+            std::fs::write(origin_path.join("file.txt"), content).unwrap();
+            origin.git(&["add", "-A"]).unwrap();
+            origin
+                .git(&[
+                    "-c",
+                    "user.name=test",
+                    "-c",
+                    "user.email=test@example.com",
+                    "commit",
+                    "--quiet",
+                    "-m",
+                    content,
+                ])
+                .unwrap();
+            commits.push(
+                origin
+                    .git(&["rev-parse", "HEAD"])
+                    .unwrap()
+                    .trim()
+                    .to_string(),
+            );
+        }
+        let url = format!("file://{}", origin_path.display());
+
+        // Full SHA
+        let full_path = temp_dir.path().join("full");
+        std::fs::create_dir(&full_path).unwrap();
+        let full = Context::new(&Some(&full_path)).unwrap();
+        full.checkout_commit(&url, &commits[0]).unwrap();
+        let content = std::fs::read_to_string(full_path.join("file.txt")).unwrap();
+        assert_eq!(content, "first");
+
+        // Abbreviated SHA
+        let short_path = temp_dir.path().join("short");
+        std::fs::create_dir(&short_path).unwrap();
+        let short = Context::new(&Some(&short_path)).unwrap();
+        short.checkout_commit(&url, &commits[0][..7]).unwrap();
+        let content = std::fs::read_to_string(short_path.join("file.txt")).unwrap();
+        assert_eq!(content, "first");
+    }
+
+    #[test]
+    fn test_merge_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| temp_dir.path().join(name);
+        std::fs::write(path("base"), "a\nb\nc\n").unwrap();
+        std::fs::write(path("current"), "a\nb\nc\nd\n").unwrap();
+        std::fs::write(path("other"), "z\nb\nc\n").unwrap();
+        let context = Context::new(&Some(temp_dir.path())).unwrap();
+
+        let labels = ["current", "base", "other"];
+        let conflicts = context
+            .merge_file(&path("current"), &path("base"), &path("other"), labels)
+            .unwrap();
+        assert!(!conflicts);
+        let merged = std::fs::read_to_string(path("current")).unwrap();
+        assert_eq!(merged, "z\nb\nc\nd\n");
+
+        std::fs::write(path("other"), "y\nb\nc\n").unwrap();
+        let conflicts = context
+            .merge_file(&path("current"), &path("other"), &path("base"), labels)
+            .unwrap();
+        assert!(conflicts);
+        let merged = std::fs::read_to_string(path("current")).unwrap();
+        assert!(merged.contains("<<<<<<< current"));
+    }
 }
@@ -0,0 +1,479 @@
+/// Splits a line of a `.properties` file into its key and value, unless it's a
+/// comment
+fn parse_property(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    if line.starts_with('#') || line.starts_with('!') {
+        return None;
+    }
+    line.split_once(['=', ':'])
+        .map(|(key, value)| (key.trim_end(), value.trim()))
+}
+
+/// Returns the value of `key` in the contents of a `.properties` file
+pub fn get_property(properties: &str, key: &str) -> Option<String> {
+    properties
+        .lines()
+        .filter_map(parse_property)
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Sets `key` to `value` in the contents of a `.properties` file, keeping
+/// comments and the order of the other properties. Returns whether the key
+/// was found.
+pub fn set_property(properties: &mut String, key: &str, value: &str) -> bool {
+    let mut found = false;
+    let lines = properties
+        .lines()
+        .map(|line| match parse_property(line) {
+            Some((name, _)) if name == key => {
+                found = true;
+                format!("{}={}", key, value)
+            }
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    if found {
+        let trailing_newline = properties.ends_with('\n');
+        *properties = lines.join("\n");
+        if trailing_newline {
+            properties.push('\n');
+        }
+    }
+    found
+}
+
+/// Sets the version of the plugin `id` in a `plugins` block of a Groovy build
+/// script (e.g., `id 'fabric-loom' version '1.2-SNAPSHOT'`). Returns whether
+/// the plugin was found.
+pub fn set_plugin_version(build: &mut String, id: &str, version: &str) -> bool {
+    let mut found = false;
+    let lines = build
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let declares_plugin = [format!("id '{}'", id), format!("id \"{}\"", id)]
+                .iter()
+                .any(|prefix| trimmed.starts_with(prefix.as_str()));
+            match trimmed.find(" version ") {
+                // Versions read from gradle.properties are left alone
+                Some(index) if declares_plugin && !trimmed.contains("${") => {
+                    found = true;
+                    let indent = &line[..line.len() - trimmed.len()];
+                    format!("{}{} version '{}'", indent, &trimmed[..index], version)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if found {
+        let trailing_newline = build.ends_with('\n');
+        *build = lines.join("\n");
+        if trailing_newline {
+            build.push('\n');
+        }
+    }
+    found
+}
+
+/// Whether a Groovy build script applies the plugin `id`, in a `plugins` block
+/// or with `apply plugin`
+pub fn applies_plugin(build: &str, id: &str) -> bool {
+    build.lines().any(|line| {
+        let line = line.trim();
+        [
+            format!("id '{}'", id),
+            format!("id \"{}\"", id),
+            format!("apply plugin: '{}'", id),
+            format!("apply plugin: \"{}\"", id),
+        ]
+        .iter()
+        .any(|declaration| {
+            line.strip_prefix(declaration.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+        })
+    })
+}
+
+/// Adds `settings` at the start of the top-level block `name` (e.g., `loom`) of
+/// a Groovy build script, which is created before `dependencies` if needed
+fn add_to_block(build: &mut String, name: &str, settings: &[String]) {
+    let indent = |line: &String| {
+        if line.is_empty() {
+            line.clone()
+        } else {
+            format!("\t{}", line)
+        }
+    };
+
+    let trailing_newline = build.ends_with('\n');
+    let mut lines = build
+        .lines()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+    let opening = format!("{} {{", name);
+    if let Some(index) = lines.iter().position(|line| line.trim_end() == opening) {
+        let mut inserted = settings.iter().map(indent).collect::<Vec<_>>();
+        inserted.push("".to_string());
+        lines.splice(index + 1..index + 1, inserted);
+    } else {
+        let mut block = vec![opening];
+        block.extend(settings.iter().map(indent));
+        block.push("}".to_string());
+        block.push("".to_string());
+        let index = lines
+            .iter()
+            .position(|line| line.trim_end() == "dependencies {")
+            .unwrap_or(lines.len());
+        lines.splice(index..index, block);
+    }
+
+    *build = lines.join("\n");
+    if trailing_newline {
+        build.push('\n');
+    }
+}
+
+/// Sets up Loom's split environment source sets in a Groovy build script: adds
+/// `splitEnvironmentSourceSets()` and the mod's source sets to the `loom`
+/// block. Returns whether the script changed (i.e., the source sets weren't
+/// split already).
+pub fn split_environment_source_sets(build: &mut String, mod_id: &str) -> bool {
+    if build.contains("splitEnvironmentSourceSets()") {
+        return false;
+    }
+
+    add_to_block(
+        build,
+        "loom",
+        &[
+            "splitEnvironmentSourceSets()".to_string(),
+            "".to_string(),
+            "mods {".to_string(),
+            format!("\t\"{}\" {{", mod_id),
+            "\t\tsourceSet sourceSets.main".to_string(),
+            "\t\tsourceSet sourceSets.client".to_string(),
+            "\t}".to_string(),
+            "}".to_string(),
+        ],
+    );
+    true
+}
+
+/// Stops Loom from generating the IDE run configuration `name` (`client` or
+/// `server`)
+pub fn disable_run_config(build: &mut String, name: &str) {
+    add_to_block(
+        build,
+        "loom",
+        &[
+            "runs {".to_string(),
+            format!("\t{} {{", name),
+            "\t\tideConfigGenerated false".to_string(),
+            "\t}".to_string(),
+            "}".to_string(),
+        ],
+    );
+}
+
+/// Sets up the Fabric API data generation (`fabricApi { configureDataGeneration() }`)
+/// in a Groovy build script. Returns whether the script changed.
+pub fn configure_data_generation(build: &mut String) -> bool {
+    if build.contains("configureDataGeneration(") {
+        return false;
+    }
+
+    add_to_block(
+        build,
+        "fabricApi",
+        &["configureDataGeneration()".to_string()],
+    );
+    true
+}
+
+/// Points Loom to the access widener at `path` (relative to the project) in a
+/// Groovy build script. Returns whether the script changed (i.e., it didn't
+/// declare an access widener already).
+pub fn set_access_widener_path(build: &mut String, path: &str) -> bool {
+    if build.contains("accessWidenerPath") {
+        return false;
+    }
+
+    add_to_block(
+        build,
+        "loom",
+        &[format!("accessWidenerPath = file(\"{}\")", path)],
+    );
+    true
+}
+
+/// Replaces the Java version following each occurrence of `marker` in
+/// `line`, either a number or a legacy `1<separator><number>` version
+fn replace_version_after(line: &str, marker: &str, separator: char, version: &str) -> String {
+    let mut result = String::new();
+    let mut rest = line;
+    while let Some(index) = rest.find(marker) {
+        let end = index + marker.len();
+        result.push_str(&rest[..end]);
+        rest = &rest[end..];
+
+        let mut after = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+        if after.len() == rest.len() {
+            // Not a version, e.g. a variable
+            continue;
+        }
+        if &rest[..rest.len() - after.len()] == "1" {
+            if let Some(minor) = after.strip_prefix(separator) {
+                let trimmed = minor.trim_start_matches(|c: char| c.is_ascii_digit());
+                if trimmed.len() < minor.len() {
+                    after = trimmed;
+                }
+            }
+        }
+        rest = after;
+        result.push_str(version);
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Sets the Java version targeted by a Groovy build script: `options.release`,
+/// `sourceCompatibility`/`targetCompatibility` and the Kotlin `jvmTarget`.
+/// Java 8 and older are written in their legacy `1.x` form where needed.
+pub fn set_java_version(build: &mut String, version: u32) {
+    let release = version.to_string();
+    // Gradle names Java 9 and 10 VERSION_1_9 and VERSION_1_10
+    let java_version = match version {
+        ..=10 => format!("1_{}", version),
+        _ => version.to_string(),
+    };
+    let jvm_target = match version {
+        ..=8 => format!("1.{}", version),
+        _ => version.to_string(),
+    };
+
+    let trailing_newline = build.ends_with('\n');
+    *build = build
+        .lines()
+        .map(|line| {
+            let line = replace_version_after(line, "options.release = ", '.', &release);
+            let line = replace_version_after(&line, "JavaVersion.VERSION_", '_', &java_version);
+            replace_version_after(&line, "jvmTarget = \"", '.', &jvm_target)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if trailing_newline {
+        build.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn test_set_property() {
+        let mut properties =
+            "# minecraft_version=1.0\nminecraft_version=1.19.4\nloader_version = 0.14.19\n"
+                .to_string();
+
+        assert!(set_property(&mut properties, "minecraft_version", "1.19.2"));
+        assert!(set_property(&mut properties, "loader_version", "0.14.22"));
+        assert!(!set_property(
+            &mut properties,
+            "loom_version",
+            "1.2-SNAPSHOT"
+        ));
+        assert_eq!(
+            properties,
+            "# minecraft_version=1.0\nminecraft_version=1.19.2\nloader_version=0.14.22\n"
+        );
+    }
+
+    #[test]
+    fn test_get_property() {
+        let properties = "# maven_group=a\nmaven_group = com.example\narchives_base_name:example\n";
+        assert_eq!(
+            get_property(properties, "maven_group").as_deref(),
+            Some("com.example")
+        );
+        assert_eq!(
+            get_property(properties, "archives_base_name").as_deref(),
+            Some("example")
+        );
+        assert_eq!(get_property(properties, "mod_version"), None);
+    }
+
+    #[test]
+    fn test_set_plugin_version() {
+        let mut build =
+            "plugins {\n\tid 'fabric-loom' version '1.2-SNAPSHOT'\n\tid 'maven-publish'\n}\n"
+                .to_string();
+
+        assert!(set_plugin_version(
+            &mut build,
+            "fabric-loom",
+            "1.5-SNAPSHOT"
+        ));
+        assert!(!set_plugin_version(&mut build, "maven-publish", "1.0"));
+        assert_eq!(
+            build,
+            "plugins {\n\tid 'fabric-loom' version '1.5-SNAPSHOT'\n\tid 'maven-publish'\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_applies_plugin() {
+        let build = "plugins {\n\tid 'fabric-loom' version '1.2-SNAPSHOT'\n\tid 'scala'\n}\napply plugin: \"idea\"\n";
+        assert!(applies_plugin(build, "fabric-loom"));
+        assert!(applies_plugin(build, "scala"));
+        assert!(applies_plugin(build, "idea"));
+        assert!(!applies_plugin(build, "fabric"));
+        assert!(!applies_plugin(build, "org.jetbrains.kotlin.jvm"));
+    }
+
+    #[test]
+    fn test_split_environment_source_sets() {
+        let mut build = "repositories {\n}\n\ndependencies {\n}\n".to_string();
+
+        assert!(split_environment_source_sets(&mut build, "example-mod"));
+        assert_eq!(
+            build,
+            concat!(
+                "repositories {\n",
+                "}\n",
+                "\n",
+                "loom {\n",
+                "\tsplitEnvironmentSourceSets()\n",
+                "\n",
+                "\tmods {\n",
+                "\t\t\"example-mod\" {\n",
+                "\t\t\tsourceSet sourceSets.main\n",
+                "\t\t\tsourceSet sourceSets.client\n",
+                "\t\t}\n",
+                "\t}\n",
+                "}\n",
+                "\n",
+                "dependencies {\n",
+                "}\n",
+            )
+        );
+        assert!(!split_environment_source_sets(&mut build, "example-mod"));
+
+        let mut build = "loom {\n\taccessWidenerPath = file(\"a\")\n}\n".to_string();
+        assert!(split_environment_source_sets(&mut build, "example-mod"));
+        assert!(build.starts_with("loom {\n\tsplitEnvironmentSourceSets()\n"));
+        assert!(build.ends_with("\t}\n\n\taccessWidenerPath = file(\"a\")\n}\n"));
+    }
+
+    #[test]
+    fn test_disable_run_config() {
+        let mut build = "dependencies {\n}\n".to_string();
+        disable_run_config(&mut build, "server");
+        assert_eq!(
+            build,
+            concat!(
+                "loom {\n",
+                "\truns {\n",
+                "\t\tserver {\n",
+                "\t\t\tideConfigGenerated false\n",
+                "\t\t}\n",
+                "\t}\n",
+                "}\n",
+                "\n",
+                "dependencies {\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_configure_data_generation() {
+        let mut build = "loom {\n}\n\ndependencies {\n}\n".to_string();
+        assert!(configure_data_generation(&mut build));
+        assert_eq!(
+            build,
+            "loom {\n}\n\nfabricApi {\n\tconfigureDataGeneration()\n}\n\ndependencies {\n}\n"
+        );
+        assert!(!configure_data_generation(&mut build));
+    }
+
+    #[test]
+    fn test_set_access_widener_path() {
+        let mut build = "loom {\n\tsplitEnvironmentSourceSets()\n}\n".to_string();
+        assert!(set_access_widener_path(
+            &mut build,
+            "src/main/resources/a.accesswidener"
+        ));
+        assert_eq!(
+            build,
+            "loom {\n\taccessWidenerPath = file(\"src/main/resources/a.accesswidener\")\n\n\tsplitEnvironmentSourceSets()\n}\n"
+        );
+        assert!(!set_access_widener_path(
+            &mut build,
+            "src/main/resources/b.accesswidener"
+        ));
+    }
+
+    #[rstest]
+    #[case("17", "VERSION_17", "\"17\"", 21, "21", "VERSION_21", "\"21\"")]
+    #[case("8", "VERSION_1_8", "\"1.8\"", 17, "17", "VERSION_17", "\"17\"")]
+    #[case("17", "VERSION_17", "\"17\"", 8, "8", "VERSION_1_8", "\"1.8\"")]
+    fn test_set_java_version(
+        #[case] old_release: &str,
+        #[case] old_java_version: &str,
+        #[case] old_jvm_target: &str,
+        #[case] version: u32,
+        #[case] release: &str,
+        #[case] java_version: &str,
+        #[case] jvm_target: &str,
+    ) {
+        let mut build = format!(
+            concat!(
+                "tasks.withType(JavaCompile).configureEach {{\n",
+                "\tit.options.release = {}\n",
+                "}}\n",
+                "compileKotlin {{\n",
+                "\tkotlinOptions.jvmTarget = {}\n",
+                "}}\n",
+                "java {{\n",
+                "\tsourceCompatibility = JavaVersion.{}\n",
+                "\ttargetCompatibility = JavaVersion.{}\n",
+                "}}\n",
+            ),
+            old_release, old_jvm_target, old_java_version, old_java_version
+        );
+
+        set_java_version(&mut build, version);
+        assert_eq!(
+            build,
+            format!(
+                concat!(
+                    "tasks.withType(JavaCompile).configureEach {{\n",
+                    "\tit.options.release = {}\n",
+                    "}}\n",
+                    "compileKotlin {{\n",
+                    "\tkotlinOptions.jvmTarget = {}\n",
+                    "}}\n",
+                    "java {{\n",
+                    "\tsourceCompatibility = JavaVersion.{}\n",
+                    "\ttargetCompatibility = JavaVersion.{}\n",
+                    "}}\n",
+                ),
+                release, jvm_target, java_version, java_version
+            )
+        );
+    }
+
+    #[test]
+    fn test_set_java_version_variable() {
+        // The release of the 1.16 template is a variable, which is left alone
+        let mut build = "\tit.options.release = targetVersion\n".to_string();
+        set_java_version(&mut build, 8);
+        assert_eq!(build, "\tit.options.release = targetVersion\n");
+    }
+}
@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use serde_json::json;
+
+use crate::{
+    code::{entrypoint::EntrypointKind, language::Language},
+    fabric::{Environment, Options},
+    minecraft,
+    template::{self, Template},
+};
+
+/// Name of the lockfile in the project root
+pub const FILE_NAME: &str = ".make-fabric-mod.lock";
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error {
+            message: format!("Invalid {}: {}", FILE_NAME, error),
+        }
+    }
+}
+
+impl From<minecraft::Error> for Error {
+    fn from(error: minecraft::Error) -> Self {
+        Error {
+            message: format!("Invalid {}: {}", FILE_NAME, error),
+        }
+    }
+}
+
+impl From<template::Error> for Error {
+    fn from(error: template::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Records how a project was generated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lockfile {
+    /// Version of make-fabric-mod that generated the project
+    pub tool_version: String,
+    /// Commit SHA of the template, if it was a git repository
+    pub commit: Option<String>,
+    pub options: Options,
+}
+
+impl Lockfile {
+    pub fn write(&self, project: &Path) -> Result<(), Error> {
+        std::fs::write(project.join(FILE_NAME), self.contents()?)?;
+        Ok(())
+    }
+
+    /// The lockfile as written to the project
+    pub fn contents(&self) -> Result<String, Error> {
+        let options = &self.options;
+        let lockfile = json!({
+            "tool_version": self.tool_version,
+            "template": {
+                "source": options.template.source(),
+                "ref": options.template_ref,
+                "commit": self.commit,
+            },
+            "meta": options.meta,
+            "mod": {
+                "id": options.mod_id,
+                "name": options.name,
+                "main_class": options.main_class,
+                "language": options.language.to_string(),
+                "entrypoint_kind": options.entrypoint_kind.map(|kind| kind.to_string()),
+                "client_class": options.client_class,
+                "split_sources": options.split_sources,
+                "environment": options.environment.to_string(),
+                "datagen": options.datagen,
+                "access_widener": options.access_widener,
+                "minecraft_version": options.minecraft_version.to_string(),
+            },
+        });
+
+        Ok(serde_json::to_string_pretty(&lockfile)? + "\n")
+    }
+
+    pub fn read(project: &Path) -> Result<Self, Error> {
+        let path = project.join(FILE_NAME);
+        if !path.exists() {
+            return Err(Error {
+                message: format!("{} not found in {}", FILE_NAME, project.display()),
+            });
+        }
+
+        let lockfile: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let string = |value: &serde_json::Value, key: &str| -> Result<String, Error> {
+            value.as_str().map(|s| s.to_string()).ok_or_else(|| Error {
+                message: format!("Invalid {}: missing `{}`", FILE_NAME, key),
+            })
+        };
+
+        let template = &lockfile["template"];
+        let mod_ = &lockfile["mod"];
+        let language = string(&mod_["language"], "mod.language")?;
+        Ok(Lockfile {
+            tool_version: string(&lockfile["tool_version"], "tool_version")?,
+            commit: template["commit"].as_str().map(|s| s.to_string()),
+            options: Options {
+                mod_id: string(&mod_["id"], "mod.id")?,
+                name: string(&mod_["name"], "mod.name")?,
+                main_class: string(&mod_["main_class"], "mod.main_class")?,
+                language: Language::from_name(&language).ok_or_else(|| Error {
+                    message: format!("Invalid {}: unknown language {}", FILE_NAME, language),
+                })?,
+                minecraft_version: string(&mod_["minecraft_version"], "mod.minecraft_version")?
+                    .parse()?,
+                template: Template::parse(&string(&template["source"], "template.source")?)?,
+                template_ref: template["ref"].as_str().map(|s| s.to_string()),
+                meta: lockfile["meta"].as_str().map(|s| s.to_string()),
+                entrypoint_kind: match mod_["entrypoint_kind"].as_str() {
+                    Some(kind) => Some(EntrypointKind::from_name(kind).ok_or_else(|| Error {
+                        message: format!("Invalid {}: unknown entrypoint kind {}", FILE_NAME, kind),
+                    })?),
+                    None => None,
+                },
+                client_class: mod_["client_class"].as_str().map(|s| s.to_string()),
+                split_sources: mod_["split_sources"].as_bool().unwrap_or(false),
+                environment: match mod_["environment"].as_str() {
+                    Some(environment) => {
+                        Environment::from_name(environment).ok_or_else(|| Error {
+                            message: format!(
+                                "Invalid {}: unknown environment {}",
+                                FILE_NAME, environment
+                            ),
+                        })?
+                    }
+                    None => Environment::Both,
+                },
+                datagen: mod_["datagen"].as_bool().unwrap_or(false),
+                access_widener: mod_["access_widener"].as_bool().unwrap_or(false),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fabric::tests::example_options;
+
+    #[test]
+    fn test_write() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let lockfile = Lockfile {
+            tool_version: "0.1.1".to_string(),
+            commit: Some("d9831a95c9af78987a58cfd0bee1158fc5240be0".to_string()),
+            options: Options {
+                template: Template::Git(
+                    "https://github.com/clabe45/fabric-example-mod-kotlin".to_string(),
+                ),
+                template_ref: Some("1.19".to_string()),
+                entrypoint_kind: Some(EntrypointKind::Object),
+                ..example_options(Language::KOTLIN)
+            },
+        };
+
+        lockfile.write(temp_dir.path()).unwrap();
+        let contents = std::fs::read_to_string(temp_dir.path().join(FILE_NAME)).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(json["tool_version"], "0.1.1");
+        assert_eq!(
+            json["template"]["source"],
+            "https://github.com/clabe45/fabric-example-mod-kotlin"
+        );
+        assert_eq!(json["template"]["ref"], "1.19");
+        assert_eq!(
+            json["template"]["commit"],
+            "d9831a95c9af78987a58cfd0bee1158fc5240be0"
+        );
+        assert_eq!(json["mod"]["id"], "example-mod");
+        assert_eq!(json["mod"]["name"], "Example Mod");
+        assert_eq!(json["mod"]["main_class"], "net.fabricmc.example.ExampleMod");
+        assert_eq!(json["mod"]["language"], "kotlin");
+        assert_eq!(json["mod"]["entrypoint_kind"], "object");
+        assert_eq!(json["mod"]["minecraft_version"], "1.19");
+    }
+
+    #[test]
+    fn test_read() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let lockfile = Lockfile {
+            tool_version: "0.1.1".to_string(),
+            commit: None,
+            options: Options {
+                datagen: true,
+                access_widener: true,
+                ..example_options(Language::JAVA)
+            },
+        };
+
+        lockfile.write(temp_dir.path()).unwrap();
+        assert_eq!(Lockfile::read(temp_dir.path()).unwrap(), lockfile);
+    }
+
+    #[test]
+    fn test_read_missing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(Lockfile::read(temp_dir.path()).is_err());
+    }
+}
@@ -1,8 +1,21 @@
+mod access_widener;
+mod cache;
 mod cli;
 mod code;
 mod fabric;
 mod file;
 mod git;
+mod gradle;
+mod lockfile;
+mod meta;
+mod minecraft;
+mod mixin;
+mod plan;
+mod port;
+mod rename;
+mod staging;
+mod template;
+mod update;
 
 fn main() {
     cli::cli().unwrap();
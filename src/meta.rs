@@ -0,0 +1,229 @@
+use std::{path::Path, process::Command};
+
+use serde_json::Value;
+
+use crate::minecraft::MinecraftVersion;
+
+/// Snapshot of the Fabric meta versions, used when `--meta` isn't given
+const BUNDLED: &str = include_str!("../data/fabric-meta.json");
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Dependency versions for a Minecraft version. Missing entries are left as
+/// the template has them.
+#[derive(Debug, PartialEq)]
+pub struct Versions {
+    pub yarn_mappings: Option<String>,
+    pub loader_version: Option<String>,
+    pub fabric_version: Option<String>,
+    pub loom_version: Option<String>,
+}
+
+/// Versions of yarn, Fabric Loader, Fabric API and Loom, in the format of
+/// Fabric meta (newest first):
+///
+/// ```json
+/// {
+///   "yarn": [{ "gameVersion": "1.19.4", "build": 2, "version": "1.19.4+build.2", "stable": true }],
+///   "loader": [{ "version": "0.14.19", "stable": true }],
+///   "fabricApi": [{ "gameVersion": "1.19.4", "version": "0.80.0+1.19.4" }],
+///   "loom": [{ "version": "1.2-SNAPSHOT", "minGameVersion": "1.19", "maxGameVersion": "1.20.1" }]
+/// }
+/// ```
+pub struct Meta {
+    versions: Value,
+}
+
+impl Meta {
+    /// Loads the versions from a file or an http(s) URL, or the bundled
+    /// snapshot if `source` is `None`
+    pub fn load(source: Option<&str>) -> Result<Self, Error> {
+        let contents = match source {
+            None => BUNDLED.to_string(),
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                download(url)?
+            }
+            Some(path) => std::fs::read_to_string(Path::new(path)).map_err(|e| Error {
+                message: format!("Could not read {}: {}", path, e),
+            })?,
+        };
+
+        Self::parse(&contents).map_err(|e| Error {
+            message: format!(
+                "Invalid version metadata in {}: {}",
+                source.unwrap_or("the bundled snapshot"),
+                e
+            ),
+        })
+    }
+
+    fn parse(contents: &str) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            versions: serde_json::from_str(contents)?,
+        })
+    }
+
+    fn entries(&self, key: &str) -> impl Iterator<Item = &Value> {
+        self.versions[key].as_array().into_iter().flatten()
+    }
+
+    fn yarn_mappings(&self, minecraft_version: &str) -> Option<String> {
+        self.entries("yarn")
+            .filter(|entry| entry["gameVersion"] == minecraft_version)
+            .max_by_key(|entry| (entry["stable"] != false, entry["build"].as_u64()))
+            .and_then(|entry| entry["version"].as_str())
+            .map(|version| version.to_string())
+    }
+
+    fn loader_version(&self) -> Option<String> {
+        self.entries("loader")
+            .find(|entry| entry["stable"] != false)
+            .and_then(|entry| entry["version"].as_str())
+            .map(|version| version.to_string())
+    }
+
+    fn fabric_version(&self, minecraft_version: &str) -> Option<String> {
+        self.entries("fabricApi")
+            .find(|entry| entry["gameVersion"] == minecraft_version)
+            .and_then(|entry| entry["version"].as_str())
+            .map(|version| version.to_string())
+    }
+
+    fn loom_version(&self, minecraft_version: &MinecraftVersion) -> Option<String> {
+        let bound = |entry: &Value, key: &str| {
+            entry[key]
+                .as_str()
+                .and_then(|version| version.parse::<MinecraftVersion>().ok())
+        };
+        self.entries("loom")
+            .find(|entry| {
+                bound(entry, "minGameVersion").is_none_or(|min| min <= *minecraft_version)
+                    && bound(entry, "maxGameVersion").is_none_or(|max| *minecraft_version <= max)
+            })
+            .and_then(|entry| entry["version"].as_str())
+            .map(|version| version.to_string())
+    }
+
+    /// Picks the newest compatible versions for `minecraft_version`
+    pub fn resolve(&self, minecraft_version: &MinecraftVersion) -> Versions {
+        let exact = minecraft_version.to_string();
+        Versions {
+            yarn_mappings: self.yarn_mappings(&exact),
+            loader_version: self.loader_version(),
+            fabric_version: self.fabric_version(&exact),
+            loom_version: self.loom_version(minecraft_version),
+        }
+    }
+}
+
+fn download(url: &str) -> Result<String, Error> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", url])
+        .output()
+        .map_err(|e| Error {
+            message: format!("Could not run curl to download {}: {}", url, e),
+        })?;
+    if !output.status.success() {
+        return Err(Error {
+            message: format!(
+                "Could not download {}: {}",
+                url,
+                String::from_utf8(output.stderr)?.trim()
+            ),
+        });
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_bundled() {
+        let meta = Meta::load(None).unwrap();
+        let versions = meta.resolve(&"1.19.4".parse().unwrap());
+        assert_eq!(versions.yarn_mappings.as_deref(), Some("1.19.4+build.2"));
+        assert_eq!(versions.fabric_version.as_deref(), Some("0.87.2+1.19.4"));
+        assert_eq!(versions.loom_version.as_deref(), Some("1.2-SNAPSHOT"));
+        assert!(versions.loader_version.is_some());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let meta = Meta::parse(
+            r#"{
+                "yarn": [
+                    { "gameVersion": "1.20", "build": 1, "version": "1.20+build.1", "stable": true },
+                    { "gameVersion": "1.19.2", "build": 27, "version": "1.19.2+build.27", "stable": true },
+                    { "gameVersion": "1.19.2", "build": 28, "version": "1.19.2+build.28", "stable": true }
+                ],
+                "loader": [
+                    { "version": "0.15.0-beta.1", "stable": false },
+                    { "version": "0.14.22", "stable": true }
+                ],
+                "fabricApi": [{ "gameVersion": "1.19.2", "version": "0.77.0+1.19.2" }],
+                "loom": [
+                    { "version": "1.5-SNAPSHOT", "minGameVersion": "1.20" },
+                    { "version": "1.2-SNAPSHOT", "minGameVersion": "1.19", "maxGameVersion": "1.19.4" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            meta.resolve(&"1.19.2".parse().unwrap()),
+            Versions {
+                yarn_mappings: Some("1.19.2+build.28".to_string()),
+                loader_version: Some("0.14.22".to_string()),
+                fabric_version: Some("0.77.0+1.19.2".to_string()),
+                loom_version: Some("1.2-SNAPSHOT".to_string()),
+            }
+        );
+
+        let versions = meta.resolve(&"1.18.2".parse().unwrap());
+        assert_eq!(versions.yarn_mappings, None);
+        assert_eq!(versions.fabric_version, None);
+        assert_eq!(versions.loom_version, None);
+    }
+
+    #[test]
+    fn test_load_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("meta.json");
+        std::fs::write(&path, r#"{ "loader": [{ "version": "0.14.0" }] }"#).unwrap();
+
+        let meta = Meta::load(Some(path.to_str().unwrap())).unwrap();
+        let versions = meta.resolve(&"1.19".parse().unwrap());
+        assert_eq!(versions.loader_version.as_deref(), Some("0.14.0"));
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(Meta::load(Some(path.to_str().unwrap())).is_err());
+        assert!(Meta::load(Some("missing.json")).is_err());
+    }
+}
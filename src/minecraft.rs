@@ -0,0 +1,286 @@
+use std::{cmp::Reverse, str::FromStr};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// The first snapshot of each release, used to find the release a snapshot
+/// leads up to. Must be sorted.
+const SNAPSHOTS: &[((u32, u32), MinecraftVersion)] = &[
+    ((18, 43), MinecraftVersion::release(1, 14, 0)),
+    ((19, 34), MinecraftVersion::release(1, 15, 0)),
+    ((20, 6), MinecraftVersion::release(1, 16, 0)),
+    ((20, 45), MinecraftVersion::release(1, 17, 0)),
+    ((21, 37), MinecraftVersion::release(1, 18, 0)),
+    ((22, 11), MinecraftVersion::release(1, 19, 0)),
+    ((22, 24), MinecraftVersion::release(1, 19, 1)),
+    ((22, 42), MinecraftVersion::release(1, 19, 3)),
+    ((23, 3), MinecraftVersion::release(1, 19, 4)),
+    ((23, 12), MinecraftVersion::release(1, 20, 0)),
+    ((23, 31), MinecraftVersion::release(1, 20, 2)),
+    ((23, 40), MinecraftVersion::release(1, 20, 3)),
+    ((24, 3), MinecraftVersion::release(1, 20, 5)),
+    ((24, 18), MinecraftVersion::release(1, 21, 0)),
+    ((24, 33), MinecraftVersion::release(1, 21, 2)),
+    ((24, 44), MinecraftVersion::release(1, 21, 4)),
+    ((25, 2), MinecraftVersion::release(1, 21, 5)),
+    ((25, 15), MinecraftVersion::release(1, 21, 6)),
+];
+
+/// The Java version required from each release on. Must be sorted.
+const JAVA_VERSIONS: &[(MinecraftVersion, u32)] = &[
+    (MinecraftVersion::release(1, 17, 0), 16),
+    (MinecraftVersion::release(1, 18, 0), 17),
+    (MinecraftVersion::release(1, 20, 5), 21),
+];
+
+/// How far along the development of a release a version is. Declared in
+/// chronological order, so the derived ordering sorts them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// e.g., `23w13a` (year, week, letter)
+    Snapshot(u32, u32, char),
+    /// e.g., `1.20-pre2`
+    PreRelease(u32),
+    /// e.g., `1.20-rc1`
+    ReleaseCandidate(u32),
+    Release,
+}
+
+/// A Minecraft release, patch release, snapshot, pre-release or release
+/// candidate. Snapshots are placed before the release they lead up to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MinecraftVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    pub stage: Stage,
+}
+
+impl MinecraftVersion {
+    pub const fn release(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            stage: Stage::Release,
+        }
+    }
+
+    /// Name of the template branch for this version (e.g., `1.19` for
+    /// `1.19.4`)
+    pub fn template_branch(&self) -> String {
+        format!("{}.{}", self.major, self.minor)
+    }
+
+    /// The Java version Minecraft requires. Snapshots and pre-releases are
+    /// treated like the release they lead up to.
+    pub fn java_version(&self) -> u32 {
+        let release = Self::release(self.major, self.minor, self.patch);
+        JAVA_VERSIONS
+            .iter()
+            .rev()
+            .find(|(first, _)| *first <= release)
+            .map_or(8, |(_, java)| *java)
+    }
+
+    /// Returns the version in `versions` closest to this one, preferring the
+    /// newer one on ties
+    pub fn nearest<'a>(&self, versions: &'a [MinecraftVersion]) -> Option<&'a MinecraftVersion> {
+        versions.iter().min_by_key(|version| {
+            (
+                version.major.abs_diff(self.major),
+                version.minor.abs_diff(self.minor),
+                version.patch.abs_diff(self.patch),
+                Reverse(*version),
+            )
+        })
+    }
+
+    fn parse_snapshot(version: &str) -> Option<Self> {
+        let (year, rest) = version.split_once('w')?;
+        if year.len() != 2 || rest.len() != 3 || !rest.is_ascii() {
+            return None;
+        }
+
+        let year = year.parse().ok()?;
+        let week = rest[..2].parse().ok()?;
+        let letter = rest.chars().nth(2).filter(|c| c.is_ascii_lowercase())?;
+
+        // Snapshots newer than the table are assumed to lead up to the
+        // latest known release
+        let (_, release) = SNAPSHOTS
+            .iter()
+            .rev()
+            .find(|(first, _)| *first <= (year, week))?;
+        Some(Self {
+            stage: Stage::Snapshot(year, week, letter),
+            ..*release
+        })
+    }
+
+    fn parse_release(version: &str) -> Option<Self> {
+        let (release, stage) = match version.split_once('-') {
+            Some((release, suffix)) => {
+                let stage = if let Some(n) = suffix.strip_prefix("pre") {
+                    Stage::PreRelease(n.parse().ok()?)
+                } else if let Some(n) = suffix.strip_prefix("rc") {
+                    Stage::ReleaseCandidate(n.parse().ok()?)
+                } else {
+                    return None;
+                };
+                (release, stage)
+            }
+            None => (version, Stage::Release),
+        };
+
+        let parts = release
+            .split('.')
+            .map(|part| {
+                // Reject signs, whitespace and leading zeros
+                let valid = part.chars().all(|c| c.is_ascii_digit())
+                    && !part.is_empty()
+                    && (part == "0" || !part.starts_with('0'));
+                valid.then(|| part.parse::<u32>().ok()).flatten()
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let (major, minor, patch) = match parts[..] {
+            [major, minor] => (major, minor, 0),
+            [major, minor, patch] => (major, minor, patch),
+            _ => return None,
+        };
+
+        Some(Self {
+            major,
+            minor,
+            patch,
+            stage,
+        })
+    }
+}
+
+impl FromStr for MinecraftVersion {
+    type Err = Error;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        Self::parse_snapshot(version)
+            .or_else(|| Self::parse_release(version))
+            .ok_or_else(|| Error {
+                message: format!("Invalid Minecraft version: {}", version),
+            })
+    }
+}
+
+impl std::fmt::Display for MinecraftVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Stage::Snapshot(year, week, letter) = self.stage {
+            return write!(f, "{:02}w{:02}{}", year, week, letter);
+        }
+
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if self.patch != 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        match self.stage {
+            Stage::PreRelease(n) => write!(f, "-pre{}", n),
+            Stage::ReleaseCandidate(n) => write!(f, "-rc{}", n),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn version(version: &str) -> MinecraftVersion {
+        version.parse().unwrap()
+    }
+
+    #[rstest]
+    #[case("1.17", MinecraftVersion::release(1, 17, 0))]
+    #[case("1.17.1", MinecraftVersion::release(1, 17, 1))]
+    #[case("1.20-pre2", MinecraftVersion { stage: Stage::PreRelease(2), ..MinecraftVersion::release(1, 20, 0) })]
+    #[case("1.20.1-rc1", MinecraftVersion { stage: Stage::ReleaseCandidate(1), ..MinecraftVersion::release(1, 20, 1) })]
+    #[case("23w13a", MinecraftVersion { stage: Stage::Snapshot(23, 13, 'a'), ..MinecraftVersion::release(1, 20, 0) })]
+    #[case("22w24a", MinecraftVersion { stage: Stage::Snapshot(22, 24, 'a'), ..MinecraftVersion::release(1, 19, 1) })]
+    fn test_parse(#[case] input: &str, #[case] expected: MinecraftVersion) {
+        assert_eq!(version(input), expected);
+        assert_eq!(version(input).to_string(), input);
+    }
+
+    #[rstest]
+    #[case("1")]
+    #[case("1.17.1.1")]
+    #[case("1.x")]
+    #[case("1.017")]
+    #[case("1.20-beta1")]
+    #[case("1.20-pre")]
+    #[case("23w13")]
+    #[case("10w01a")]
+    #[case("")]
+    fn test_parse_invalid(#[case] input: &str) {
+        assert!(input.parse::<MinecraftVersion>().is_err());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let versions = [
+            "1.16.5",
+            "1.17",
+            "1.17.1",
+            "22w11a",
+            "22w12a",
+            "1.19-pre1",
+            "1.19-pre2",
+            "1.19-rc1",
+            "1.19",
+            "1.19.4",
+            "23w13a",
+            "1.20",
+        ];
+        for pair in versions.windows(2) {
+            assert!(version(pair[0]) < version(pair[1]), "{:?}", pair);
+        }
+    }
+
+    #[rstest]
+    #[case("1.19", "1.19")]
+    #[case("1.19.4", "1.19")]
+    #[case("1.20-rc1", "1.20")]
+    #[case("23w03a", "1.19")]
+    fn test_template_branch(#[case] input: &str, #[case] branch: &str) {
+        assert_eq!(version(input).template_branch(), branch);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let versions = ["1.17", "1.18", "1.19", "1.20"].map(version);
+        assert_eq!(version("1.16").nearest(&versions), Some(&version("1.17")));
+        assert_eq!(version("1.19.4").nearest(&versions), Some(&version("1.19")));
+        assert_eq!(version("1.21").nearest(&versions), Some(&version("1.20")));
+        assert_eq!(version("1.21").nearest(&[]), None);
+    }
+
+    #[rstest]
+    #[case("1.16.5", 8)]
+    #[case("1.17", 16)]
+    #[case("1.17.1", 16)]
+    #[case("1.18", 17)]
+    #[case("1.20.4", 17)]
+    #[case("1.20.5", 21)]
+    #[case("1.20.5-pre1", 21)]
+    #[case("1.21", 21)]
+    fn test_java_version(#[case] input: &str, #[case] java: u32) {
+        assert_eq!(version(input).java_version(), java);
+    }
+}
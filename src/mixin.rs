@@ -0,0 +1,643 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    code::{language::Language, lexer},
+    fabric::{self, Environment},
+};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Array of the mixin config listing the mixins applied in `environment`
+fn array_name(environment: Environment) -> &'static str {
+    match environment {
+        Environment::Client => "client",
+        Environment::Server => "server",
+        Environment::Both => "mixins",
+    }
+}
+
+/// Finds the mixin config to register a mixin applied in `environment` in:
+/// the client config of split source sets for client mixins, or the mod's
+/// common config otherwise
+fn find_config(path: &Path, environment: Environment) -> Result<PathBuf, Error> {
+    let config_path = path.join("src/main/resources/fabric.mod.json");
+    if !config_path.exists() {
+        return Err(Error {
+            message: format!("Not a mod: {}", path.display()),
+        });
+    }
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(config_path)?)?;
+
+    // Entries are either a file name or an object with a "config" key
+    let entries = config["mixins"].as_array().cloned().unwrap_or_default();
+    let name_of = |entry: &serde_json::Value| {
+        entry
+            .as_str()
+            .or_else(|| entry["config"].as_str())
+            .map(|name| name.to_string())
+    };
+    let client = entries
+        .iter()
+        .filter(|entry| environment == Environment::Client && entry["environment"] == "client")
+        .find_map(name_of);
+    let common = entries
+        .iter()
+        .filter(|entry| entry["environment"].is_null() || entry["environment"] == "*")
+        .find_map(name_of);
+    client
+        .or(common)
+        .and_then(|name| fabric::find_resource(path, &name))
+        .ok_or_else(|| Error {
+            message: "No mixin config found in fabric.mod.json".to_string(),
+        })
+}
+
+/// Names the handler of an injection into `method` after it, without its
+/// descriptor. Constructors and static initializers get a name of their own
+/// (e.g., `onInit` for `<init>`).
+fn handler_name(method: &str) -> Result<String, Error> {
+    let handler = match method.split('(').next().unwrap() {
+        "<init>" => "onInit",
+        "<clinit>" => "onClinit",
+        name => name,
+    };
+    if !lexer::is_java_identifier(handler) {
+        return Err(Error {
+            message: format!("Invalid method: {}", method),
+        });
+    }
+    Ok(handler.to_string())
+}
+
+/// Generates the source of a mixin into `target`, with an injection at the
+/// start of `inject` if given
+fn mixin_source(
+    package: &str,
+    name: &str,
+    target: &str,
+    inject: Option<&str>,
+) -> Result<String, Error> {
+    let target_name = target.rsplit('.').next().unwrap();
+    let mut imports = vec![
+        target.to_string(),
+        "org.spongepowered.asm.mixin.Mixin".to_string(),
+    ];
+    let mut body = String::new();
+    if let Some(method) = inject {
+        imports.extend(
+            [
+                "org.spongepowered.asm.mixin.injection.At",
+                "org.spongepowered.asm.mixin.injection.Inject",
+                "org.spongepowered.asm.mixin.injection.callback.CallbackInfo",
+            ]
+            .map(|import| import.to_string()),
+        );
+        let handler = handler_name(method)?;
+        body = format!(
+            "\t@Inject(at = @At(\"HEAD\"), method = \"{}\")\n\
+             \tprivate void {}(CallbackInfo info) {{\n\
+             \t\t// This code is injected into the start of {}.{}\n\
+             \t}}\n",
+            method, handler, target_name, method
+        );
+    }
+
+    let imports = imports
+        .iter()
+        .map(|import| format!("import {};\n", import))
+        .collect::<String>();
+    Ok(format!(
+        "package {};\n\n{}\n@Mixin({}.class)\npublic class {} {{\n{}}}\n",
+        package, imports, target_name, name, body
+    ))
+}
+
+/// Inserts `name` into the array `key` of `config`, keeping it sorted.
+/// Returns false if the name is already there.
+fn insert_sorted(config: &mut serde_json::Value, key: &str, name: &str) -> bool {
+    let mut names = config[key]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.as_str().map(|name| name.to_string()))
+        .collect::<Vec<_>>();
+    if names.iter().any(|existing| existing == name) {
+        return false;
+    }
+    names.push(name.to_string());
+    names.sort();
+    config[key] = serde_json::json!(names);
+    true
+}
+
+/// Returns the simple name of the fully qualified class `target`
+fn simple_name(target: &str) -> Result<&str, Error> {
+    match target.rsplit_once('.') {
+        Some((_, name)) if !name.is_empty() => Ok(name),
+        _ => Err(Error {
+            message: format!("The target class must be fully qualified: {}", target),
+        }),
+    }
+}
+
+/// Writes the class `name` generated by `source` (from the package) in the
+/// Java module of the mod at `path`, and registers it in the mixin config for
+/// `environment`. Returns the path of the new class.
+fn register(
+    path: &Path,
+    environment: Environment,
+    name: &str,
+    source: impl FnOnce(&str) -> Result<String, Error>,
+) -> Result<PathBuf, Error> {
+    if !lexer::is_java_identifier(name) {
+        return Err(Error {
+            message: format!("Invalid mixin name: {}", name),
+        });
+    }
+
+    let config_path = find_config(path, environment)?;
+    let mut config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+    let package = config["package"]
+        .as_str()
+        .ok_or_else(|| Error {
+            message: format!("No package in {}", config_path.display()),
+        })?
+        .to_string();
+
+    // Mixins are written in Java, in the source set of their config (e.g.,
+    // src/client/java for src/client/resources)
+    let root = config_path.parent().unwrap().parent().unwrap();
+    let class_path = root
+        .join(Language::JAVA.source_dir())
+        .join(package.replace('.', "/"))
+        .join(format!("{}.{}", name, Language::JAVA.extension()));
+    if class_path.exists() || !insert_sorted(&mut config, array_name(environment), name) {
+        return Err(Error {
+            message: format!("Mixin already exists: {}.{}", package, name),
+        });
+    }
+
+    let source = source(&package)?;
+    std::fs::create_dir_all(class_path.parent().unwrap())?;
+    std::fs::write(&class_path, source)?;
+    std::fs::write(&config_path, serde_json::to_string_pretty(&config)?)?;
+    Ok(class_path)
+}
+
+/// Generates a mixin into the fully qualified class `target` and registers it
+/// in the mixin config for `environment`. The mixin is named after the target
+/// unless `name` is given. Returns the path of the new class.
+pub fn add_mixin(
+    path: &Path,
+    target: &str,
+    environment: Environment,
+    name: Option<&str>,
+    inject: Option<&str>,
+) -> Result<PathBuf, Error> {
+    let target_name = simple_name(target)?;
+    let name = name
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("{}Mixin", target_name));
+    register(path, environment, &name, |package| {
+        mixin_source(package, &name, target, inject)
+    })
+}
+
+/// Capitalizes the first letter of a field or method name (e.g., `count` in
+/// `getCount`)
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Returns how `java_type` is written in the source, adding it to `imports`
+/// if it's fully qualified (e.g., `net.minecraft.item.ItemStack[]`). Generic
+/// types are written as given.
+fn type_name(java_type: &str, imports: &mut BTreeSet<String>) -> String {
+    let java_type = java_type.trim();
+    let base = java_type.trim_end_matches("[]");
+    if java_type.contains('<') || !base.contains('.') {
+        return java_type.to_string();
+    }
+    imports.insert(base.to_string());
+    java_type[base.rfind('.').unwrap() + 1..].to_string()
+}
+
+/// Generates the source of an accessor interface into `target`. Fields are
+/// given as `name:Type` and methods as `name(Type,...)ReturnType`.
+fn accessor_source(
+    package: &str,
+    name: &str,
+    target: &str,
+    fields: &[String],
+    methods: &[String],
+) -> Result<String, Error> {
+    let target_name = target.rsplit('.').next().unwrap();
+    let mut imports = BTreeSet::from([
+        target.to_string(),
+        "org.spongepowered.asm.mixin.Mixin".to_string(),
+    ]);
+    let mut members = Vec::new();
+    for field in fields {
+        let (field_name, field_type) = field.split_once(':').ok_or_else(|| Error {
+            message: format!("Invalid field: {} (expected name:Type)", field),
+        })?;
+        let field_type = type_name(field_type, &mut imports);
+        imports.insert("org.spongepowered.asm.mixin.gen.Accessor".to_string());
+        let capitalized = capitalize(field_name);
+        members.push(format!(
+            "\t@Accessor(\"{}\")\n\t{} get{}();\n",
+            field_name, field_type, capitalized
+        ));
+        members.push(format!(
+            "\t@Accessor(\"{}\")\n\tvoid set{}({} {});\n",
+            field_name, capitalized, field_type, field_name
+        ));
+    }
+    for method in methods {
+        let invalid = || Error {
+            message: format!(
+                "Invalid method: {} (expected name(Type,...)ReturnType)",
+                method
+            ),
+        };
+        let (method_name, rest) = method.split_once('(').ok_or_else(invalid)?;
+        let (args, return_type) = rest.split_once(')').ok_or_else(invalid)?;
+        if return_type.trim().is_empty() {
+            return Err(invalid());
+        }
+        let return_type = type_name(return_type, &mut imports);
+        let args = args
+            .split(',')
+            .filter(|arg| !arg.trim().is_empty())
+            .enumerate()
+            .map(|(index, arg)| format!("{} arg{}", type_name(arg, &mut imports), index))
+            .collect::<Vec<_>>()
+            .join(", ");
+        imports.insert("org.spongepowered.asm.mixin.gen.Invoker".to_string());
+        members.push(format!(
+            "\t@Invoker(\"{}\")\n\t{} invoke{}({});\n",
+            method_name,
+            return_type,
+            capitalize(method_name),
+            args
+        ));
+    }
+
+    let imports = imports
+        .iter()
+        .map(|import| format!("import {};\n", import))
+        .collect::<String>();
+    Ok(format!(
+        "package {};\n\n{}\n@Mixin({}.class)\npublic interface {} {{\n{}}}\n",
+        package,
+        imports,
+        target_name,
+        name,
+        members.join("\n")
+    ))
+}
+
+/// Generates an accessor interface exposing `fields` and `methods` of the
+/// fully qualified class `target`, and registers it in the mixin config. The
+/// interface is named after the target unless `name` is given. Returns the
+/// path of the new interface.
+pub fn add_accessor(
+    path: &Path,
+    target: &str,
+    fields: &[String],
+    methods: &[String],
+    name: Option<&str>,
+) -> Result<PathBuf, Error> {
+    let target_name = simple_name(target)?;
+    let name = name
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("{}Accessor", target_name));
+    if fields.is_empty() && methods.is_empty() {
+        return Err(Error {
+            message: "Nothing to access: give at least one --field or --method".to_string(),
+        });
+    }
+    register(path, Environment::Both, &name, |package| {
+        accessor_source(package, &name, target, fields, methods)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::fabric::{
+        tests::{create_mod, example_options},
+        Options,
+    };
+
+    fn read_json(path: &Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_mixin_source() {
+        assert_eq!(
+            mixin_source(
+                "net.fabricmc.example.mixin",
+                "TitleScreenMixin",
+                "net.minecraft.client.gui.screen.TitleScreen",
+                Some("init()V")
+            )
+            .unwrap(),
+            fs::read_to_string(
+                "templates/java/src/main/java/net/fabricmc/example/mixin/ExampleMixin.java"
+            )
+            .unwrap()
+            .replace("ExampleMixin", "TitleScreenMixin")
+        );
+    }
+
+    #[rstest]
+    #[case("<init>", "onInit")]
+    #[case("<clinit>()V", "onClinit")]
+    #[case("tick", "tick")]
+    fn test_mixin_source_handler(#[case] method: &str, #[case] handler: &str) {
+        let source = mixin_source(
+            "net.fabricmc.example.mixin",
+            "TitleScreenMixin",
+            "net.minecraft.client.gui.screen.TitleScreen",
+            Some(method),
+        )
+        .unwrap();
+        assert!(source.contains(&format!("method = \"{}\"", method)));
+        assert!(source.contains(&format!("private void {}(CallbackInfo info)", handler)));
+    }
+
+    #[rstest]
+    #[case("", None)]
+    #[case("1Mixin", None)]
+    #[case("Mixin-A", None)]
+    #[case("TitleScreenMixin", Some("1tick"))]
+    fn test_add_mixin_invalid(#[case] name: &str, #[case] inject: Option<&str>) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_mod(&path, &example_options(Language::JAVA));
+
+        let config_path = path.join("src/main/resources/example-mod.mixins.json");
+        let config = read_json(&config_path);
+        let target = "net.minecraft.client.gui.screen.TitleScreen";
+        assert!(add_mixin(&path, target, Environment::Both, Some(name), inject).is_err());
+        assert_eq!(read_json(&config_path), config);
+    }
+
+    #[rstest]
+    #[case(Language::JAVA, Environment::Both, "mixins")]
+    #[case(Language::KOTLIN, Environment::Client, "client")]
+    #[case(Language::SCALA, Environment::Server, "server")]
+    fn test_add_mixin(
+        #[case] language: Language,
+        #[case] environment: Environment,
+        #[case] array: &str,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_mod(&path, &example_options(language));
+
+        let class_path = add_mixin(
+            &path,
+            "net.minecraft.server.MinecraftServer",
+            environment,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            class_path,
+            path.join("src/main/java/net/fabricmc/example/mixin/MinecraftServerMixin.java")
+        );
+        let source = fs::read_to_string(class_path).unwrap();
+        assert!(
+            source.contains("@Mixin(MinecraftServer.class)\npublic class MinecraftServerMixin {")
+        );
+
+        let config = read_json(&path.join("src/main/resources/example-mod.mixins.json"));
+        assert!(config[array]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("MinecraftServerMixin")));
+
+        // Adding it twice fails
+        assert!(add_mixin(
+            &path,
+            "net.minecraft.server.MinecraftServer",
+            environment,
+            None,
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_add_mixin_sorted() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_mod(&path, &example_options(Language::JAVA));
+
+        for name in ["B", "C", "A"] {
+            add_mixin(
+                &path,
+                "net.minecraft.client.MinecraftClient",
+                Environment::Client,
+                Some(name),
+                None,
+            )
+            .unwrap();
+        }
+        let config = read_json(&path.join("src/main/resources/example-mod.mixins.json"));
+        assert_eq!(
+            config["client"],
+            serde_json::json!(["A", "B", "C", "ExampleMixin"])
+        );
+    }
+
+    #[test]
+    fn test_add_mixin_split_sources() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_mod(
+            &path,
+            &Options {
+                split_sources: true,
+                ..example_options(Language::JAVA)
+            },
+        );
+
+        let class_path = add_mixin(
+            &path,
+            "net.minecraft.client.MinecraftClient",
+            Environment::Client,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            class_path,
+            path.join(
+                "src/client/java/net/fabricmc/example/mixin/client/MinecraftClientMixin.java"
+            )
+        );
+        let config = read_json(&path.join("src/client/resources/example-mod.client.mixins.json"));
+        assert_eq!(
+            config["client"],
+            serde_json::json!(["MinecraftClientMixin"])
+        );
+    }
+
+    #[test]
+    fn test_add_mixin_unqualified_target() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_mod(&path, &example_options(Language::JAVA));
+
+        assert!(add_mixin(&path, "MinecraftClient", Environment::Both, None, None).is_err());
+    }
+
+    #[test]
+    fn test_accessor_source() {
+        let source = accessor_source(
+            "net.fabricmc.example.mixin",
+            "LivingEntityAccessor",
+            "net.minecraft.entity.LivingEntity",
+            &[
+                "jumping:boolean".to_string(),
+                "activeItemStack:net.minecraft.item.ItemStack".to_string(),
+            ],
+            &[
+                "getNextAirUnderwater(int)int".to_string(),
+                "tickStatusEffects()void".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            source,
+            concat!(
+                "package net.fabricmc.example.mixin;\n",
+                "\n",
+                "import net.minecraft.entity.LivingEntity;\n",
+                "import net.minecraft.item.ItemStack;\n",
+                "import org.spongepowered.asm.mixin.Mixin;\n",
+                "import org.spongepowered.asm.mixin.gen.Accessor;\n",
+                "import org.spongepowered.asm.mixin.gen.Invoker;\n",
+                "\n",
+                "@Mixin(LivingEntity.class)\n",
+                "public interface LivingEntityAccessor {\n",
+                "\t@Accessor(\"jumping\")\n",
+                "\tboolean getJumping();\n",
+                "\n",
+                "\t@Accessor(\"jumping\")\n",
+                "\tvoid setJumping(boolean jumping);\n",
+                "\n",
+                "\t@Accessor(\"activeItemStack\")\n",
+                "\tItemStack getActiveItemStack();\n",
+                "\n",
+                "\t@Accessor(\"activeItemStack\")\n",
+                "\tvoid setActiveItemStack(ItemStack activeItemStack);\n",
+                "\n",
+                "\t@Invoker(\"getNextAirUnderwater\")\n",
+                "\tint invokeGetNextAirUnderwater(int arg0);\n",
+                "\n",
+                "\t@Invoker(\"tickStatusEffects\")\n",
+                "\tvoid invokeTickStatusEffects();\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[rstest]
+    #[case("jumping", "")]
+    #[case("", "tick")]
+    #[case("", "tick()")]
+    fn test_accessor_source_invalid(#[case] field: &str, #[case] method: &str) {
+        let fields = [field.to_string()];
+        let methods = [method.to_string()];
+        assert!(accessor_source(
+            "net.fabricmc.example.mixin",
+            "EntityAccessor",
+            "net.minecraft.entity.Entity",
+            if field.is_empty() { &[] } else { &fields },
+            if method.is_empty() { &[] } else { &methods },
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_add_accessor() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_mod(&path, &example_options(Language::KOTLIN));
+
+        let class_path = add_accessor(
+            &path,
+            "net.minecraft.entity.Entity",
+            &["fireTicks:int".to_string()],
+            &[],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            class_path,
+            path.join("src/main/java/net/fabricmc/example/mixin/EntityAccessor.java")
+        );
+        let source = fs::read_to_string(class_path).unwrap();
+        assert!(source.contains("public interface EntityAccessor {"));
+
+        let config = read_json(&path.join("src/main/resources/example-mod.mixins.json"));
+        assert_eq!(config["mixins"], serde_json::json!(["EntityAccessor"]));
+
+        // An accessor without members or with an invalid one isn't written
+        assert!(add_accessor(&path, "net.minecraft.entity.Entity", &[], &[], Some("A")).is_err());
+        let fields = ["fireTicks".to_string()];
+        assert!(add_accessor(
+            &path,
+            "net.minecraft.entity.Entity",
+            &fields,
+            &[],
+            Some("B")
+        )
+        .is_err());
+        let config = read_json(&path.join("src/main/resources/example-mod.mixins.json"));
+        assert_eq!(config["mixins"], serde_json::json!(["EntityAccessor"]));
+    }
+}
@@ -0,0 +1,329 @@
+use std::path::{Path, PathBuf};
+
+use crate::{file, git};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<file::Error> for Error {
+    fn from(error: file::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<git::Error> for Error {
+    fn from(error: git::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// A filesystem or git mutation. Paths are relative to the mod directory.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// Copies the files of a template checkout, without its history
+    Clone {
+        template: String,
+        checkout: PathBuf,
+    },
+    /// Removes a file or a directory
+    Delete(PathBuf),
+    /// Moves a file or a directory, removing the parent directories it leaves
+    /// empty
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    RewriteFile {
+        path: PathBuf,
+        contents: String,
+    },
+    EditJson {
+        path: PathBuf,
+        value: serde_json::Value,
+    },
+    GitInit,
+}
+
+impl Operation {
+    /// Lists the files inside the directory the operation works on (the
+    /// template checkout, or a deleted or renamed directory), relative to the
+    /// mod directory. Operations on a single file list nothing.
+    fn directory_files(&self, root: &Path) -> Result<Vec<PathBuf>, Error> {
+        let (dir, prefix) = match self {
+            Operation::Clone { checkout, .. } => (checkout.clone(), PathBuf::new()),
+            Operation::Delete(path) => (root.join(path), path.clone()),
+            Operation::Rename { from, to } => (root.join(from), to.clone()),
+            _ => return Ok(Vec::new()),
+        };
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        Ok(file::list_files(&dir)?
+            .into_iter()
+            .map(|file| prefix.join(file))
+            .collect())
+    }
+
+    fn apply(&self, root: &Path) -> Result<(), Error> {
+        match self {
+            Operation::Clone { checkout, .. } => {
+                let is_empty = root.read_dir().map_or(true, |mut dir| dir.next().is_none());
+                if !is_empty {
+                    return Err(Error {
+                        message: format!("Destination {} already exists", root.display()),
+                    });
+                }
+                file::copy_dir(checkout, root)?;
+            }
+            Operation::Delete(path) => {
+                let path = root.join(path);
+                if path.is_dir() {
+                    std::fs::remove_dir_all(path)?;
+                } else if path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+            }
+            Operation::Rename { from, to } => {
+                let (from, to) = (root.join(from), root.join(to));
+                std::fs::create_dir_all(to.parent().unwrap())?;
+                std::fs::rename(&from, to)?;
+                file::remove_empty_parent_dirs(&from)?;
+            }
+            Operation::RewriteFile { path, contents } => {
+                let path = root.join(path);
+                std::fs::create_dir_all(path.parent().unwrap())?;
+                std::fs::write(path, contents)?;
+            }
+            Operation::EditJson { path, value } => {
+                let path = root.join(path);
+                std::fs::create_dir_all(path.parent().unwrap())?;
+                std::fs::write(path, serde_json::to_string_pretty(value)?)?;
+            }
+            Operation::GitInit => {
+                git::Context::new(&Some(root))?.git(&["init"])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Clone { template, .. } => write!(f, "clone {}", template),
+            Operation::Delete(path) => write!(f, "delete {}", path.display()),
+            Operation::Rename { from, to } => {
+                write!(f, "rename {} -> {}", from.display(), to.display())
+            }
+            Operation::RewriteFile { path, .. } => write!(f, "rewrite {}", path.display()),
+            Operation::EditJson { path, .. } => write!(f, "edit {}", path.display()),
+            Operation::GitInit => write!(f, "git init"),
+        }
+    }
+}
+
+/// A sequence of operations, built up in a working directory. Each operation
+/// is applied to the working directory as it's added, so later steps see the
+/// effects of earlier ones, and can then be replayed into another directory.
+pub struct Plan {
+    root: PathBuf,
+    operations: Vec<Operation>,
+    /// The files inside the directory each operation works on
+    files: Vec<Vec<PathBuf>>,
+}
+
+impl Plan {
+    /// Creates a plan working in `root`, which is modified as operations are
+    /// added
+    pub fn new(root: &Path) -> Self {
+        Plan {
+            root: root.to_path_buf(),
+            operations: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// The working directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Joins `relative` to the working directory
+    pub fn path(&self, relative: impl AsRef<Path>) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    /// The operations with the files they affect inside a directory (e.g., the
+    /// files copied by the clone, or moved by a rename). Files in the mod
+    /// directory are listed at the path they end up at.
+    pub fn operation_files(&self) -> impl Iterator<Item = (&Operation, &[PathBuf])> {
+        self.operations
+            .iter()
+            .zip(self.files.iter().map(Vec::as_slice))
+    }
+
+    fn relative(&self, path: &Path) -> Result<PathBuf, Error> {
+        path.strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+            .map_err(|_| Error {
+                message: format!("{} is outside of {}", path.display(), self.root.display()),
+            })
+    }
+
+    fn push(&mut self, operation: Operation) -> Result<(), Error> {
+        // List the files first, as a deleted or renamed directory is gone
+        // once the operation is applied
+        let files = operation.directory_files(&self.root)?;
+        operation.apply(&self.root)?;
+        self.operations.push(operation);
+        self.files.push(files);
+        Ok(())
+    }
+
+    pub fn clone_template(&mut self, template: &str, checkout: &Path) -> Result<(), Error> {
+        self.push(Operation::Clone {
+            template: template.to_string(),
+            checkout: checkout.to_path_buf(),
+        })
+    }
+
+    pub fn delete(&mut self, path: &Path) -> Result<(), Error> {
+        let path = self.relative(path)?;
+        self.push(Operation::Delete(path))
+    }
+
+    pub fn rename(&mut self, from: &Path, to: &Path) -> Result<(), Error> {
+        let from = self.relative(from)?;
+        let to = self.relative(to)?;
+        self.push(Operation::Rename { from, to })
+    }
+
+    pub fn write(&mut self, path: &Path, contents: impl Into<String>) -> Result<(), Error> {
+        let path = self.relative(path)?;
+        self.push(Operation::RewriteFile {
+            path,
+            contents: contents.into(),
+        })
+    }
+
+    pub fn write_json(&mut self, path: &Path, value: &serde_json::Value) -> Result<(), Error> {
+        let path = self.relative(path)?;
+        self.push(Operation::EditJson {
+            path,
+            value: value.clone(),
+        })
+    }
+
+    pub fn git_init(&mut self) -> Result<(), Error> {
+        self.push(Operation::GitInit)
+    }
+
+    /// Replays the operations into `target`
+    pub fn execute(&self, target: &Path) -> Result<(), Error> {
+        for operation in &self.operations {
+            operation.apply(target)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let checkout = temp_dir.path().join("checkout");
+        fs::create_dir_all(checkout.join("src/a")).unwrap();
+        fs::write(checkout.join("src/a/A.java"), "class A {}").unwrap();
+        fs::write(checkout.join("LICENSE"), "CC0").unwrap();
+        fs::write(checkout.join("config.json"), "{}").unwrap();
+
+        let work = temp_dir.path().join("work");
+        let mut plan = Plan::new(&work);
+        plan.clone_template("example", &checkout).unwrap();
+        plan.delete(&work.join("LICENSE")).unwrap();
+        plan.rename(&work.join("src/a"), &work.join("src/b/c"))
+            .unwrap();
+        plan.write(&work.join("src/b/c/A.java"), "class B {}")
+            .unwrap();
+        plan.write_json(&work.join("config.json"), &serde_json::json!({ "id": "b" }))
+            .unwrap();
+        let operations: Vec<String> = plan
+            .operation_files()
+            .map(|(op, _)| op.to_string())
+            .collect();
+        assert_eq!(
+            operations,
+            [
+                "clone example",
+                "delete LICENSE",
+                "rename src/a -> src/b/c",
+                "rewrite src/b/c/A.java",
+                "edit config.json",
+            ]
+        );
+        let files: Vec<&[PathBuf]> = plan.operation_files().map(|(_, files)| files).collect();
+        assert_eq!(
+            files,
+            [
+                &[
+                    PathBuf::from("LICENSE"),
+                    PathBuf::from("config.json"),
+                    PathBuf::from("src/a/A.java")
+                ][..],
+                &[],
+                &[PathBuf::from("src/b/c/A.java")],
+                &[],
+                &[],
+            ]
+        );
+
+        // Replaying the plan gives the same files as the working directory
+        let target = temp_dir.path().join("target");
+        plan.execute(&target).unwrap();
+        assert_eq!(
+            file::list_files(&target).unwrap(),
+            file::list_files(&work).unwrap()
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("src/b/c/A.java")).unwrap(),
+            "class B {}"
+        );
+        assert!(!target.join("src/a").exists());
+
+        // The destination must be empty
+        assert!(plan.execute(&target).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    cache::Cache,
+    fabric::{self, Options},
+    file, git,
+    lockfile::{self, Lockfile},
+    minecraft::MinecraftVersion,
+    plan::{self, Plan},
+    update::{self, Change, Regenerated},
+};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<update::Error> for Error {
+    fn from(error: update::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<fabric::Error> for Error {
+    fn from(error: fabric::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<lockfile::Error> for Error {
+    fn from(error: lockfile::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<git::Error> for Error {
+    fn from(error: git::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<file::Error> for Error {
+    fn from(error: file::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<plan::Error> for Error {
+    fn from(error: plan::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// What a port changed, and what it left for the user
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Build files the template changes were applied to
+    pub changes: Vec<(PathBuf, Change)>,
+    /// Other files the template changed, which were left untouched
+    pub not_applied: Vec<PathBuf>,
+}
+
+/// Whether a file (relative to the project root) belongs to the Gradle build
+fn is_build_file(file: &Path) -> bool {
+    file.starts_with("gradle")
+        || matches!(
+            file.to_str(),
+            Some("build.gradle")
+                | Some("settings.gradle")
+                | Some("gradle.properties")
+                | Some("gradlew")
+                | Some("gradlew.bat")
+        )
+}
+
+/// Updates the Minecraft and Java versions in the configs of the project at
+/// `root`. The changes are planned in a copy of its sources, then replayed into
+/// the project.
+fn update_version_fields(root: &Path, minecraft_version: &MinecraftVersion) -> Result<(), Error> {
+    let work = tempfile::tempdir()?;
+    file::copy_dir(&root.join("src"), &work.path().join("src"))?;
+    let mut plan = Plan::new(work.path());
+    fabric::update_version_fields(&mut plan, minecraft_version)?;
+    plan.execute(root)?;
+    Ok(())
+}
+
+/// Ports the mod at `path` to `minecraft_version`. The build files are merged
+/// with the difference between the template for the mod's version and the
+/// one for the new version, and the Minecraft and Java versions are updated
+/// in fabric.mod.json and the mixin configs. Source files aren't touched.
+pub fn port_mod(
+    path: &Path,
+    minecraft_version: &MinecraftVersion,
+    cache: &Cache,
+) -> Result<Report, Error> {
+    let lockfile = Lockfile::read(path)?;
+    let options = Options {
+        minecraft_version: *minecraft_version,
+        // The ref the mod was created with belongs to the old version
+        template_ref: None,
+        ..lockfile.options.clone()
+    };
+    let regenerated = Regenerated::new(&lockfile, &options, cache)?;
+
+    // Changes to the version fields are applied directly, so they don't need
+    // to be reported
+    for root in [regenerated.base.as_path(), path] {
+        update_version_fields(root, minecraft_version)?;
+    }
+
+    let repo = git::Context::new(&Some(path))?;
+    let mut report = Report::default();
+    for file in regenerated.changed_files()? {
+        if is_build_file(&file) {
+            if let Some(change) = regenerated.merge(path, &file, &repo)? {
+                report.changes.push((file, change));
+            }
+        } else {
+            report.not_applied.push(file);
+        }
+    }
+
+    regenerated.lockfile.write(path)?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        code::language::Language,
+        fabric::tests::example_options,
+        template::{builtin, tests::commit_all, Template},
+    };
+
+    #[test]
+    fn test_is_build_file() {
+        assert!(is_build_file(Path::new("build.gradle")));
+        assert!(is_build_file(Path::new("gradle.properties")));
+        assert!(is_build_file(Path::new(
+            "gradle/wrapper/gradle-wrapper.properties"
+        )));
+        assert!(!is_build_file(Path::new(
+            "src/main/resources/fabric.mod.json"
+        )));
+        assert!(!is_build_file(Path::new("gradle.md")));
+    }
+
+    #[test]
+    fn test_port_mod() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        builtin::write(&template_path, &Language::JAVA).unwrap();
+        commit_all(&template_path, "1.19");
+
+        // The template for the new version changes a build file, updates the
+        // wrapper and changes the main class
+        let build_path = template_path.join("build.gradle");
+        let build = fs::read_to_string(&build_path).unwrap();
+        fs::write(
+            &build_path,
+            build.replace("repositories {", "// 1.20\nrepositories {"),
+        )
+        .unwrap();
+        let wrapper_path = template_path.join("gradle/wrapper/gradle-wrapper.properties");
+        fs::write(&wrapper_path, "distributionUrl=gradle-8.7-bin.zip\n").unwrap();
+        let main_path = template_path.join("src/main/java/net/fabricmc/example/ExampleMod.java");
+        let main = fs::read_to_string(&main_path).unwrap();
+        fs::write(&main_path, main + "// 1.20\n").unwrap();
+        commit_all(&template_path, "1.20");
+
+        let path = temp_dir.path().join("example-mod");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+        let options = Options {
+            minecraft_version: "1.19.4".parse().unwrap(),
+            template: Template::parse(template_path.to_str().unwrap()).unwrap(),
+            ..example_options(Language::JAVA)
+        };
+        fabric::create_mod(&path, &options, &cache).unwrap();
+        let build_path = path.join("build.gradle");
+        let build = fs::read_to_string(&build_path).unwrap();
+        fs::write(&build_path, build + "// mod\n").unwrap();
+
+        let report = port_mod(&path, &"1.20.5".parse().unwrap(), &cache).unwrap();
+        assert_eq!(
+            report.changes,
+            vec![
+                (PathBuf::from("build.gradle"), Change::Merged),
+                (
+                    PathBuf::from("gradle/wrapper/gradle-wrapper.properties"),
+                    Change::Updated
+                ),
+                (PathBuf::from("gradle.properties"), Change::Updated),
+            ]
+        );
+        assert_eq!(
+            report.not_applied,
+            vec![PathBuf::from(
+                "src/main/java/net/fabricmc/example/ExampleMod.java"
+            )]
+        );
+
+        let build = fs::read_to_string(&build_path).unwrap();
+        assert!(build.contains("// 1.20\n"));
+        assert!(build.contains("// mod\n"));
+        assert!(build.contains("it.options.release = 21\n"));
+        let properties = fs::read_to_string(path.join("gradle.properties")).unwrap();
+        assert!(properties.contains("minecraft_version=1.20.5\n"));
+
+        let resources = path.join("src/main/resources");
+        let contents = fs::read_to_string(resources.join("fabric.mod.json")).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(config["depends"]["minecraft"], "~1.20.5");
+        assert_eq!(config["depends"]["java"], ">=21");
+        let contents = fs::read_to_string(resources.join("example-mod.mixins.json")).unwrap();
+        let mixins: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(mixins["compatibilityLevel"], "JAVA_21");
+
+        // Source files are left alone
+        let main =
+            fs::read_to_string(path.join("src/main/java/net/fabricmc/example/ExampleMod.java"))
+                .unwrap();
+        assert!(!main.contains("// 1.20"));
+
+        let lockfile = Lockfile::read(&path).unwrap();
+        assert_eq!(lockfile.options.minecraft_version.to_string(), "1.20.5");
+    }
+}
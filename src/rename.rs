@@ -0,0 +1,597 @@
+use std::path::Path;
+
+use crate::{
+    access_widener,
+    code::{
+        language::Language,
+        refactor::{self, rename_qualified_name},
+    },
+    fabric, file, git, gradle,
+    lockfile::{self, Lockfile},
+    plan::{self, Plan},
+    template::manifest::{Manifest, Rename},
+};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<fabric::Error> for Error {
+    fn from(error: fabric::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<file::Error> for Error {
+    fn from(error: file::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<lockfile::Error> for Error {
+    fn from(error: lockfile::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<plan::Error> for Error {
+    fn from(error: plan::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// What to rename. Anything left out keeps its current value.
+#[derive(Debug, Default)]
+pub struct Renames {
+    pub mod_id: Option<String>,
+    pub name: Option<String>,
+    pub package: Option<String>,
+    /// Simple or fully qualified name of the main class
+    pub main_class: Option<String>,
+}
+
+/// The names of a mod, as found in its files
+#[derive(Debug, Clone, PartialEq)]
+pub struct Names {
+    pub mod_id: String,
+    pub name: String,
+    /// Fully qualified name of the main class
+    pub main_class: String,
+    /// `maven_group` in gradle.properties
+    pub maven_group: Option<String>,
+    /// `archives_base_name` in gradle.properties
+    pub archives_base_name: Option<String>,
+}
+
+impl Names {
+    pub fn package(&self) -> &str {
+        self.main_class
+            .rsplit_once('.')
+            .map_or("", |(package, _)| package)
+    }
+
+    pub fn simple_name(&self) -> &str {
+        self.main_class.rsplit('.').next().unwrap()
+    }
+
+    /// The group derived from the package, like for new mods (e.g.,
+    /// `net.fabricmc` for `net.fabricmc.example`)
+    fn package_group(&self) -> &str {
+        self.package()
+            .rsplit_once('.')
+            .map_or("", |(group, _)| group)
+    }
+
+    /// The archive name derived from the package, like for new mods (e.g.,
+    /// `example` for `net.fabricmc.example`)
+    fn package_base_name(&self) -> &str {
+        self.package().rsplit('.').next().unwrap()
+    }
+}
+
+/// The name in lowercase, with dashes between words (e.g., `example-mod` for
+/// `Example Mod`)
+fn slug(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Renames `value` along with the name it was derived from, given as pairs of
+/// old and new names. Names that didn't change are skipped, so a value shared
+/// by several names follows the one that was renamed.
+fn follow(value: &Option<String>, derived_from: &[(String, String)]) -> Option<String> {
+    let value = value.as_ref()?;
+    let renamed = derived_from
+        .iter()
+        .find(|(old, new)| old == value && old != new)
+        .map_or(value, |(_, new)| new);
+    Some(renamed.clone())
+}
+
+fn read_json(path: &Path) -> Result<serde_json::Value, Error> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Reads the names of the mod at `path` from fabric.mod.json and
+/// gradle.properties, along with the language of its main class
+fn detect(path: &Path) -> Result<(Names, Language), Error> {
+    let config_path = path.join("src/main/resources/fabric.mod.json");
+    if !config_path.exists() {
+        return Err(Error {
+            message: format!("Not a mod: {}", path.display()),
+        });
+    }
+    let config = read_json(&config_path)?;
+
+    // Client-only mods declare their main class as a client entrypoint. Kotlin
+    // functions are referenced through their file's class (e.g.,
+    // `ExampleModKt::onInitialize`).
+    let entrypoints = &config["entrypoints"];
+    let entrypoint = if entrypoints["main"][0].is_null() {
+        &entrypoints["client"][0]
+    } else {
+        &entrypoints["main"][0]
+    };
+    let value = entrypoint
+        .as_str()
+        .or_else(|| entrypoint["value"].as_str())
+        .ok_or_else(|| Error {
+            message: "No main entrypoint in fabric.mod.json".to_string(),
+        })?;
+    let main_class = match value.split_once("::") {
+        Some((class, _)) => class.strip_suffix("Kt").unwrap_or(class),
+        None => value,
+    };
+
+    let language = Language::all()
+        .find(|language| refactor::find_class(path, language, main_class).is_some())
+        .ok_or_else(|| Error {
+            message: format!("Source file of {} not found", main_class),
+        })?;
+    let properties_path = path.join("gradle.properties");
+    let properties = if properties_path.exists() {
+        std::fs::read_to_string(properties_path)?
+    } else {
+        String::new()
+    };
+    let names = Names {
+        mod_id: config["id"].as_str().unwrap_or_default().to_string(),
+        name: config["name"].as_str().unwrap_or_default().to_string(),
+        main_class: main_class.to_string(),
+        maven_group: gradle::get_property(&properties, "maven_group"),
+        archives_base_name: gradle::get_property(&properties, "archives_base_name"),
+    };
+    Ok((names, language))
+}
+
+/// Works out the new names from the current ones. `maven_group` and
+/// `archives_base_name` follow the names they were derived from: the package
+/// for the group, and the mod ID, the name or the package for the archive.
+fn resolve(current: &Names, renames: &Renames) -> Result<Names, Error> {
+    let main_class = match (&renames.main_class, &renames.package) {
+        (Some(main_class), package) if main_class.contains('.') => {
+            let (class_package, _) = main_class.rsplit_once('.').unwrap();
+            if package
+                .as_deref()
+                .is_some_and(|package| package != class_package)
+            {
+                return Err(Error {
+                    message: format!(
+                        "{} isn't in package {}",
+                        main_class,
+                        package.as_ref().unwrap()
+                    ),
+                });
+            }
+            main_class.clone()
+        }
+        (main_class, package) => format!(
+            "{}.{}",
+            package.as_deref().unwrap_or(current.package()),
+            main_class.as_deref().unwrap_or(current.simple_name())
+        ),
+    };
+    let mut new = Names {
+        mod_id: renames.mod_id.clone().unwrap_or(current.mod_id.clone()),
+        name: renames.name.clone().unwrap_or(current.name.clone()),
+        main_class,
+        maven_group: None,
+        archives_base_name: None,
+    };
+    let pair = |old: &str, new: &str| (old.to_string(), new.to_string());
+    new.maven_group = follow(
+        &current.maven_group,
+        &[
+            pair(current.package_group(), new.package_group()),
+            pair(current.package(), new.package()),
+        ],
+    );
+    new.archives_base_name = follow(
+        &current.archives_base_name,
+        &[
+            pair(&current.mod_id, &new.mod_id),
+            (slug(&current.name), slug(&new.name)),
+            pair(current.package_base_name(), new.package_base_name()),
+        ],
+    );
+    Ok(new)
+}
+
+/// Renames the entrypoints, the icon and the access widener in
+/// fabric.mod.json
+fn update_mod_config(plan: &mut Plan, old: &Names, new: &Names) -> Result<(), Error> {
+    let config_path = plan.path("src/main/resources/fabric.mod.json");
+    let mut config = read_json(&config_path)?;
+    config["id"] = serde_json::Value::String(new.mod_id.clone());
+    config["name"] = serde_json::Value::String(new.name.clone());
+
+    let rename_class = |class: &str| {
+        // The file class of Kotlin functions is named after the main class
+        let class = rename_qualified_name(
+            class,
+            &format!("{}Kt", old.main_class),
+            &format!("{}Kt", new.main_class),
+        );
+        let class = rename_qualified_name(&class, &old.main_class, &new.main_class);
+        rename_qualified_name(&class, old.package(), new.package())
+    };
+    if let Some(entrypoints) = config["entrypoints"].as_object_mut() {
+        for entry in entrypoints
+            .values_mut()
+            .flat_map(|entries| entries.as_array_mut().into_iter().flatten())
+        {
+            let value = if entry.is_string() {
+                entry
+            } else {
+                &mut entry["value"]
+            };
+            if let Some(class) = value.as_str() {
+                *value = serde_json::Value::String(rename_class(class));
+            }
+        }
+    }
+
+    let old_icon_dir = format!("assets/{}/", old.mod_id);
+    if let Some(icon) = config["icon"].as_str() {
+        if let Some(rest) = icon.strip_prefix(&old_icon_dir) {
+            config["icon"] = serde_json::Value::String(format!("assets/{}/{}", new.mod_id, rest));
+        }
+    }
+
+    let old_widener = access_widener::file_name(&old.mod_id);
+    if config["accessWidener"] == old_widener.as_str() {
+        let new_widener = access_widener::file_name(&new.mod_id);
+        if let Some(widener_path) = fabric::find_resource(plan.root(), &old_widener) {
+            plan.rename(&widener_path, &widener_path.with_file_name(&new_widener))?;
+        }
+        config["accessWidener"] = serde_json::Value::String(new_widener);
+    }
+
+    plan.write_json(&config_path, &config)?;
+    Ok(())
+}
+
+/// Renames the mod's source set and access widener in build.gradle
+fn update_build(plan: &mut Plan, old: &Names, new: &Names) -> Result<(), Error> {
+    let build_path = plan.path("build.gradle");
+    if !build_path.exists() {
+        return Ok(());
+    }
+    let build = std::fs::read_to_string(&build_path)?
+        .replace(
+            &format!("\"{}\" {{", old.mod_id),
+            &format!("\"{}\" {{", new.mod_id),
+        )
+        .replace(
+            &format!("/{}", access_widener::file_name(&old.mod_id)),
+            &format!("/{}", access_widener::file_name(&new.mod_id)),
+        );
+    plan.write(&build_path, build)?;
+    Ok(())
+}
+
+/// Renames the mod at `path`: its ID (in string literals, the mixin configs,
+/// the assets and the access widener), its name, its package and its main
+/// class. The working tree must be clean unless `force` is set, so the
+/// changes are easy to review. Returns the old and new names.
+pub fn rename_mod(path: &Path, renames: &Renames, force: bool) -> Result<(Names, Names), Error> {
+    if !force {
+        let repo = git::Context::new(&Some(path)).map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        if !repo.is_clean().unwrap_or(false) {
+            return Err(Error {
+                message: "The working tree has uncommitted changes (or isn't a git repository). Commit them or use --force".to_string(),
+            });
+        }
+    }
+
+    let (old, language) = detect(path)?;
+    let new = resolve(&old, renames)?;
+    let mut plan = Plan::new(path);
+
+    // Describe the current names like a template's placeholders, so the mod
+    // can be refactored like a new one
+    let manifest = Manifest {
+        package: old.package().to_string(),
+        main_class: old.simple_name().to_string(),
+        mod_id: old.mod_id.clone(),
+        rename: ["src/main/resources/assets", "src/client/resources/assets"]
+            .iter()
+            .filter(|assets| path.join(assets).join(&old.mod_id).is_dir())
+            .map(|assets| Rename {
+                from: format!("{}/{}", assets, old.mod_id),
+                to: format!("{}/{{mod_id}}", assets),
+            })
+            .collect(),
+        delete: Vec::new(),
+    };
+
+    for language in language.modules() {
+        fabric::refactor_module(&mut plan, &language, &manifest, &new.main_class)?;
+        if old.mod_id != new.mod_id {
+            for module_path in refactor::module_paths(path, &language) {
+                file::recursive_replace(
+                    &mut plan,
+                    &module_path,
+                    &format!("\"{}\"", old.mod_id),
+                    &format!("\"{}\"", new.mod_id),
+                )?;
+            }
+        }
+    }
+    if old.mod_id != new.mod_id {
+        for rename in &manifest.rename {
+            plan.rename(
+                &path.join(&rename.from),
+                &path.join(rename.to.replace("{mod_id}", &new.mod_id)),
+            )?;
+        }
+    }
+
+    fabric::update_mixin_configs(&mut plan, &manifest, &new.mod_id, new.package())?;
+    update_mod_config(&mut plan, &old, &new)?;
+    update_build(&mut plan, &old, &new)?;
+    let properties_path = path.join("gradle.properties");
+    if properties_path.exists() {
+        let mut properties = std::fs::read_to_string(&properties_path)?;
+        for (key, value) in [
+            ("maven_group", &new.maven_group),
+            ("archives_base_name", &new.archives_base_name),
+        ] {
+            if let Some(value) = value {
+                gradle::set_property(&mut properties, key, value);
+            }
+        }
+        plan.write(&properties_path, properties)?;
+    }
+
+    // Keep the lockfile in sync, so updates regenerate the template with the
+    // new names
+    if path.join(lockfile::FILE_NAME).exists() {
+        let mut lockfile = Lockfile::read(path)?;
+        lockfile.options.mod_id = new.mod_id.clone();
+        lockfile.options.name = new.name.clone();
+        lockfile.options.main_class = new.main_class.clone();
+        plan.write(&path.join(lockfile::FILE_NAME), lockfile.contents()?)?;
+    }
+
+    Ok((old, new))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        fabric::{
+            tests::{create_mod, example_options},
+            Options,
+        },
+        template::tests::commit_all,
+    };
+
+    /// Creates and commits a mod with a client entrypoint, split sources and
+    /// an access widener
+    fn create_committed_mod(path: &Path, language: Language) {
+        let options = Options {
+            client_class: Some("net.fabricmc.example.ExampleModClient".to_string()),
+            split_sources: true,
+            access_widener: true,
+            ..example_options(language)
+        };
+        create_mod(path, &options);
+        commit_all(path, "main");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let current = Names {
+            mod_id: "example-mod".to_string(),
+            name: "Example Mod".to_string(),
+            main_class: "net.fabricmc.example.ExampleMod".to_string(),
+            maven_group: Some("net.fabricmc".to_string()),
+            archives_base_name: Some("example-mod".to_string()),
+        };
+        let renames = Renames {
+            package: Some("com.example".to_string()),
+            ..Renames::default()
+        };
+        let new = resolve(&current, &renames).unwrap();
+        assert_eq!(new.main_class, "com.example.ExampleMod");
+        // Derived from the package
+        assert_eq!(new.maven_group.as_deref(), Some("com"));
+        // Derived from the mod ID, which didn't change
+        assert_eq!(new.archives_base_name.as_deref(), Some("example-mod"));
+        let renames = Renames {
+            main_class: Some("Tweaks".to_string()),
+            ..Renames::default()
+        };
+        assert_eq!(
+            resolve(&current, &renames).unwrap().main_class,
+            "net.fabricmc.example.Tweaks"
+        );
+        let renames = Renames {
+            package: Some("com.example".to_string()),
+            main_class: Some("org.example.Tweaks".to_string()),
+            ..Renames::default()
+        };
+        assert!(resolve(&current, &renames).is_err());
+    }
+
+    #[test]
+    fn test_rename_mod() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_committed_mod(&path, Language::KOTLIN);
+
+        let renames = Renames {
+            mod_id: Some("tweaks".to_string()),
+            name: Some("Tweaks".to_string()),
+            package: Some("com.example.tweaks".to_string()),
+            main_class: Some("Tweaks".to_string()),
+        };
+        let (old, new) = rename_mod(&path, &renames, false).unwrap();
+        assert_eq!(old.main_class, "net.fabricmc.example.ExampleMod");
+        assert_eq!(new.main_class, "com.example.tweaks.Tweaks");
+
+        let main =
+            fs::read_to_string(path.join("src/main/kotlin/com/example/tweaks/Tweaks.kt")).unwrap();
+        assert!(main.starts_with("package com.example.tweaks"));
+        assert!(main.contains("class Tweaks"));
+        assert!(main.contains("\"tweaks\""));
+        assert!(path
+            .join("src/client/kotlin/com/example/tweaks/ExampleModClient.kt")
+            .exists());
+        assert!(path
+            .join("src/main/java/com/example/tweaks/mixin/ExampleMixin.java")
+            .exists());
+        assert!(!path.join("src/main/kotlin/net").exists());
+        assert!(path.join("src/main/resources/assets/tweaks").is_dir());
+
+        let resources = path.join("src/main/resources");
+        let config = read_json(&resources.join("fabric.mod.json")).unwrap();
+        assert_eq!(config["id"], "tweaks");
+        assert_eq!(config["name"], "Tweaks");
+        assert_eq!(config["icon"], "assets/tweaks/icon.png");
+        assert_eq!(
+            config["entrypoints"]["main"][0],
+            "com.example.tweaks.Tweaks"
+        );
+        assert_eq!(
+            config["entrypoints"]["client"][0],
+            "com.example.tweaks.ExampleModClient"
+        );
+        assert_eq!(
+            config["mixins"],
+            serde_json::json!([
+                "tweaks.mixins.json",
+                { "config": "tweaks.client.mixins.json", "environment": "client" }
+            ])
+        );
+        assert_eq!(config["accessWidener"], "tweaks.accesswidener");
+        assert!(resources.join("tweaks.accesswidener").exists());
+
+        let mixins = read_json(&resources.join("tweaks.mixins.json")).unwrap();
+        assert_eq!(mixins["package"], "com.example.tweaks.mixin");
+        let client_mixins =
+            read_json(&path.join("src/client/resources/tweaks.client.mixins.json")).unwrap();
+        assert_eq!(client_mixins["package"], "com.example.tweaks.mixin.client");
+
+        let build = fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert!(build.contains("\"tweaks\" {"));
+        assert!(build.contains("file(\"src/main/resources/tweaks.accesswidener\")"));
+        let properties = fs::read_to_string(path.join("gradle.properties")).unwrap();
+        assert!(properties.contains("maven_group=com.example\n"));
+        assert!(properties.contains("archives_base_name=tweaks\n"));
+
+        let lockfile = Lockfile::read(&path).unwrap();
+        assert_eq!(lockfile.options.mod_id, "tweaks");
+        assert_eq!(lockfile.options.main_class, "com.example.tweaks.Tweaks");
+    }
+
+    #[test]
+    fn test_rename_mod_name_only() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_mod(&path, &example_options(Language::JAVA));
+        // The archive is named after the mod
+        let properties_path = path.join("gradle.properties");
+        let mut properties = fs::read_to_string(&properties_path).unwrap();
+        gradle::set_property(&mut properties, "archives_base_name", "example-mod");
+        fs::write(&properties_path, properties).unwrap();
+        commit_all(&path, "main");
+
+        let renames = Renames {
+            name: Some("Tweaks".to_string()),
+            ..Renames::default()
+        };
+        let (old, new) = rename_mod(&path, &renames, false).unwrap();
+        assert_eq!(old.archives_base_name.as_deref(), Some("example-mod"));
+        assert_eq!(new.archives_base_name.as_deref(), Some("tweaks"));
+        assert_eq!(new.maven_group, old.maven_group);
+
+        let config = read_json(&path.join("src/main/resources/fabric.mod.json")).unwrap();
+        assert_eq!(config["name"], "Tweaks");
+        assert_eq!(config["id"], "example-mod");
+        let properties = fs::read_to_string(properties_path).unwrap();
+        assert!(properties.contains("maven_group=net.fabricmc\n"));
+        assert!(properties.contains("archives_base_name=tweaks\n"));
+        assert!(path
+            .join("src/main/java/net/fabricmc/example/ExampleMod.java")
+            .exists());
+    }
+
+    #[test]
+    fn test_rename_mod_dirty_tree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        create_committed_mod(&path, Language::JAVA);
+        fs::write(path.join("README.md"), "wip\n").unwrap();
+
+        let renames = Renames {
+            name: Some("Tweaks".to_string()),
+            ..Renames::default()
+        };
+        assert!(rename_mod(&path, &renames, false).is_err());
+        rename_mod(&path, &renames, true).unwrap();
+        let config = read_json(&path.join("src/main/resources/fabric.mod.json")).unwrap();
+        assert_eq!(config["name"], "Tweaks");
+        assert_eq!(config["id"], "example-mod");
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, Once},
+};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Staging directories in use, and whether to keep them if generation fails
+static ACTIVE: Mutex<Vec<(PathBuf, bool)>> = Mutex::new(Vec::new());
+static HANDLE_INTERRUPTS: Once = Once::new();
+
+fn clean_up(path: &Path, keep: bool) {
+    if keep {
+        eprintln!("Kept the staging directory {}", path.display());
+    } else {
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
+
+/// Cleans up the staging directories in use on Ctrl-C
+fn handle_interrupts() {
+    HANDLE_INTERRUPTS.call_once(|| {
+        let result = ctrlc::set_handler(|| {
+            for (path, keep) in ACTIVE.lock().unwrap().drain(..) {
+                clean_up(&path, keep);
+            }
+            std::process::exit(130);
+        });
+        if let Err(e) = result {
+            eprintln!("Warning: can't clean up on Ctrl-C: {}", e);
+        }
+    });
+}
+
+/// A directory next to a target directory, that is moved into place once it's
+/// complete. It's removed if it isn't, unless it's kept for debugging.
+pub struct Staging {
+    path: PathBuf,
+    keep_failed: bool,
+    finished: bool,
+}
+
+impl Staging {
+    /// Creates a hidden staging directory in the parent of `target`, so it can
+    /// be renamed to `target` atomically
+    pub fn new(target: &Path, keep_failed: bool) -> Result<Self, Error> {
+        let name = target.file_name().ok_or_else(|| Error {
+            message: format!("Invalid destination: {}", target.display()),
+        })?;
+        let parent = match target.parent() {
+            Some(parent) if parent != Path::new("") => parent,
+            _ => Path::new("."),
+        };
+        std::fs::create_dir_all(parent)?;
+
+        handle_interrupts();
+        let path = tempfile::Builder::new()
+            .prefix(&format!(".{}.staging-", name.to_string_lossy()))
+            .tempdir_in(parent)?
+            .keep();
+        ACTIVE.lock().unwrap().push((path.clone(), keep_failed));
+        Ok(Staging {
+            path,
+            keep_failed,
+            finished: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves `staged`, a directory in the staging directory, to `target`
+    pub fn finish(mut self, staged: &Path, target: &Path) -> Result<(), Error> {
+        // An empty target can be replaced
+        if target.is_dir() && target.read_dir()?.next().is_none() {
+            std::fs::remove_dir(target)?;
+        }
+        std::fs::rename(staged, target)?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        ACTIVE
+            .lock()
+            .unwrap()
+            .retain(|(path, _)| path != &self.path);
+        clean_up(&self.path, self.keep_failed && !self.finished);
+    }
+}
@@ -0,0 +1,497 @@
+pub mod builtin;
+pub mod manifest;
+
+use std::path::{Path, PathBuf};
+
+use crate::{cache, code::language::Language, file, git, minecraft::MinecraftVersion};
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    /// The template doesn't support the requested Minecraft version
+    UnsupportedVersion,
+    /// The template couldn't be downloaded (e.g., no network access)
+    Unavailable,
+    Other,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+    kind: ErrorKind,
+}
+
+impl Error {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<git::Error> for Error {
+    fn from(error: git::Error) -> Self {
+        let kind = match error.kind() {
+            git::ErrorKind::GitNotFound => ErrorKind::Unavailable,
+            _ => ErrorKind::Other,
+        };
+        Error {
+            message: error.to_string(),
+            kind,
+        }
+    }
+}
+
+impl From<cache::Error> for Error {
+    fn from(error: cache::Error) -> Self {
+        let kind = match error.kind() {
+            cache::ErrorKind::RefNotFound => ErrorKind::UnsupportedVersion,
+            cache::ErrorKind::CloneFailed | cache::ErrorKind::NotCached => ErrorKind::Unavailable,
+            _ => ErrorKind::Other,
+        };
+        Error {
+            message: error.to_string(),
+            kind,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+            kind: ErrorKind::Other,
+        }
+    }
+}
+
+impl From<file::Error> for Error {
+    fn from(error: file::Error) -> Self {
+        Error {
+            message: error.to_string(),
+            kind: ErrorKind::Other,
+        }
+    }
+}
+
+/// Where the project files are scaffolded from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Template {
+    /// A git repository, given by URL or by a local (bare or non-bare) path
+    Git(String),
+    /// A plain directory, copied as-is
+    Directory(PathBuf),
+    /// A minimal template embedded in the binary
+    Builtin(Language),
+}
+
+impl Template {
+    /// The official example mod for the given language, or its built-in
+    /// template if there is none
+    pub fn official(language: &Language) -> Self {
+        match language.backend().template {
+            Some(url) => Template::Git(url.to_string()),
+            None => Template::Builtin(*language),
+        }
+    }
+
+    /// The URL under which the template is cached, if it is a git template
+    pub fn cache_url(&self) -> Option<String> {
+        match self {
+            Template::Git(url) => Some(clone_url(url)),
+            Template::Directory(_) | Template::Builtin(_) => None,
+        }
+    }
+
+    /// An absolute form of the template that can be parsed back from any
+    /// working directory
+    pub fn source(&self) -> String {
+        match self {
+            Template::Git(url) => clone_url(url),
+            Template::Directory(path) => path
+                .canonicalize()
+                .unwrap_or_else(|_| path.to_path_buf())
+                .display()
+                .to_string(),
+            Template::Builtin(_) => self.to_string(),
+        }
+    }
+
+    /// Interprets a `--template` argument. `builtin:<language>` selects an
+    /// embedded template, local directories that are not git repositories are
+    /// copied and everything else is treated as a git remote.
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        if let Some(name) = source.strip_prefix("builtin:") {
+            return match Language::from_name(name) {
+                Some(language) => Ok(Template::Builtin(language)),
+                None => Err(Error {
+                    message: format!("Unknown built-in template: {}", name),
+                    kind: ErrorKind::Other,
+                }),
+            };
+        }
+
+        let path = Path::new(source);
+        if path.is_dir() && !is_git_repository(path) {
+            Ok(Template::Directory(path.to_path_buf()))
+        } else {
+            Ok(Template::Git(source.to_string()))
+        }
+    }
+
+    /// Lists the Minecraft versions the template has a branch for, oldest
+    /// first
+    pub fn versions(&self, cache: &cache::Cache) -> Result<Vec<MinecraftVersion>, Error> {
+        let mut versions = match self {
+            Template::Git(url) => cache
+                .branches(&clone_url(url))?
+                .iter()
+                .filter_map(|branch| branch.parse().ok())
+                .collect::<Vec<MinecraftVersion>>(),
+            Template::Directory(path) => {
+                return Err(Error {
+                    message: format!(
+                        "Directory templates don't have versions: {}",
+                        path.display()
+                    ),
+                    kind: ErrorKind::Other,
+                })
+            }
+            Template::Builtin(_) => vec![builtin::MINECRAFT_VERSION.parse().unwrap()],
+        };
+        versions.sort();
+        versions.dedup();
+        Ok(versions)
+    }
+
+    /// Copies the template into `path`. Git templates are cloned through the
+    /// cache at `reference` (a branch, tag or commit), which defaults to the
+    /// template branch of the Minecraft version. The resulting `.git`
+    /// directory is left in place. Other templates have no refs, so
+    /// `reference` must be `None`.
+    pub fn fetch(
+        &self,
+        path: &Path,
+        minecraft_version: &MinecraftVersion,
+        reference: Option<&str>,
+        cache: &cache::Cache,
+    ) -> Result<(), Error> {
+        if let (Some(reference), Template::Directory(_) | Template::Builtin(_)) = (reference, self)
+        {
+            return Err(Error {
+                message: format!(
+                    "Template ref {} can't be used with {}, which isn't a git template",
+                    reference, self
+                ),
+                kind: ErrorKind::Other,
+            });
+        }
+
+        match self {
+            Template::Git(url) => {
+                let branch = minecraft_version.template_branch();
+                let reference = reference.unwrap_or(&branch);
+                let cached_path = cache.fetch(&clone_url(url), reference)?;
+                let global = git::Context::new(&None)?;
+                global.git(&[
+                    "clone",
+                    "--quiet",
+                    cached_path.to_str().unwrap(),
+                    path.to_str().unwrap(),
+                ])?;
+            }
+            Template::Directory(template_path) => {
+                if path.exists() {
+                    return Err(Error {
+                        message: format!("Destination {} already exists", path.display()),
+                        kind: ErrorKind::Other,
+                    });
+                }
+                file::copy_dir(template_path, path)?;
+            }
+            Template::Builtin(language) => {
+                if minecraft_version.template_branch() != builtin::MINECRAFT_VERSION {
+                    return Err(Error {
+                        message: format!(
+                            "The built-in templates only support Minecraft {}",
+                            builtin::MINECRAFT_VERSION
+                        ),
+                        kind: ErrorKind::UnsupportedVersion,
+                    });
+                }
+                if path.exists() {
+                    return Err(Error {
+                        message: format!("Destination {} already exists", path.display()),
+                        kind: ErrorKind::Other,
+                    });
+                }
+                builtin::write(path, language)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Template::Git(url) => write!(f, "{}", url),
+            Template::Directory(path) => write!(f, "{}", path.display()),
+            Template::Builtin(language) => write!(f, "builtin:{}", language.to_string()),
+        }
+    }
+}
+
+fn is_git_repository(path: &Path) -> bool {
+    let is_work_tree = path.join(".git").exists();
+    let is_bare = path.join("HEAD").is_file() && path.join("objects").is_dir();
+    is_work_tree || is_bare
+}
+
+/// Local repositories are cloned through `file://` so that `--depth` is
+/// honored
+fn clone_url(url: &str) -> String {
+    let path = Path::new(url);
+    if path.is_dir() {
+        if let Ok(path) = path.canonicalize() {
+            return format!("file://{}", path.display());
+        }
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{fs, io::Write, path::Path, process::Command};
+
+    use super::*;
+    use crate::cache::Cache;
+
+    fn create_text_file(path: &Path, content: &str) {
+        // Create the directory if it doesn't exist
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+
+        // Create the file
+        let mut file = fs::File::create(path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    /// Commits everything in `path` to `branch` (starting from the current
+    /// commit), creating the repository if needed
+    pub fn commit_all(path: &Path, branch: &str) {
+        for args in [
+            vec!["init", "--quiet"],
+            vec!["checkout", "--quiet", "-B", branch],
+            vec!["add", "-A"],
+            vec![
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                "template",
+            ],
+        ] {
+            let status = Command::new("git")
+                .current_dir(path)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    #[test]
+    fn test_parse_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template = Template::parse(temp_dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(template, Template::Directory(temp_dir.path().to_path_buf()));
+    }
+
+    #[test]
+    fn test_parse_git_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        let source = temp_dir.path().to_str().unwrap();
+        assert_eq!(
+            Template::parse(source).unwrap(),
+            Template::Git(source.to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_url() {
+        let url = "https://github.com/FabricMC/fabric-example-mod";
+        assert_eq!(
+            Template::parse(url).unwrap(),
+            Template::Git(url.to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_builtin() {
+        assert_eq!(
+            Template::parse("builtin:java").unwrap(),
+            Template::Builtin(Language::JAVA)
+        );
+        assert_eq!(
+            Template::parse("builtin:kotlin").unwrap(),
+            Template::Builtin(Language::KOTLIN)
+        );
+        assert!(Template::parse("builtin:cobol").is_err());
+    }
+
+    #[test]
+    fn test_fetch_builtin_unsupported_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("mod");
+        let error = Template::Builtin(Language::JAVA)
+            .fetch(
+                &path,
+                &"1.16".parse().unwrap(),
+                None,
+                &Cache::at(&temp_dir.path().join("cache"), false),
+            )
+            .unwrap_err();
+
+        assert!(matches!(error.kind(), ErrorKind::UnsupportedVersion));
+    }
+
+    #[test]
+    fn test_fetch_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_text_file(&template_path.join("gradle.properties"), "a=b");
+
+        let path = temp_dir.path().join("mod");
+        Template::Directory(template_path)
+            .fetch(
+                &path,
+                &"1.19".parse().unwrap(),
+                None,
+                &Cache::at(&temp_dir.path().join("cache"), false),
+            )
+            .unwrap();
+
+        assert!(path.join("gradle.properties").exists());
+    }
+
+    #[test]
+    fn test_fetch_ref_of_non_git_template() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_text_file(&template_path.join("gradle.properties"), "a=b");
+
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+        for template in [
+            Template::Directory(template_path),
+            Template::Builtin(Language::JAVA),
+        ] {
+            let path = temp_dir.path().join("mod");
+            assert!(template
+                .fetch(&path, &"1.19".parse().unwrap(), Some("1.19"), &cache)
+                .is_err());
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    fn test_fetch_local_git_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_text_file(&template_path.join("gradle.properties"), "a=b");
+        commit_all(&template_path, "1.19");
+
+        let path = temp_dir.path().join("mod");
+        Template::parse(template_path.to_str().unwrap())
+            .unwrap()
+            .fetch(
+                &path,
+                &"1.19.4".parse().unwrap(),
+                None,
+                &Cache::at(&temp_dir.path().join("cache"), false),
+            )
+            .unwrap();
+
+        assert!(path.join("gradle.properties").exists());
+        assert!(path.join(".git").exists());
+    }
+
+    #[test]
+    fn test_fetch_missing_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_text_file(&template_path.join("gradle.properties"), "a=b");
+        commit_all(&template_path, "1.19");
+
+        let path = temp_dir.path().join("mod");
+        let error = Template::parse(template_path.to_str().unwrap())
+            .unwrap()
+            .fetch(
+                &path,
+                &"1.16".parse().unwrap(),
+                None,
+                &Cache::at(&temp_dir.path().join("cache"), false),
+            )
+            .unwrap_err();
+
+        assert!(matches!(error.kind(), ErrorKind::UnsupportedVersion));
+    }
+
+    #[test]
+    fn test_fetch_tag() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_text_file(&template_path.join("gradle.properties"), "a=b");
+        commit_all(&template_path, "1.19");
+        let status = Command::new("git")
+            .current_dir(&template_path)
+            .args(["tag", "v1"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let path = temp_dir.path().join("mod");
+        Template::parse(template_path.to_str().unwrap())
+            .unwrap()
+            .fetch(
+                &path,
+                &"1.20".parse().unwrap(),
+                Some("v1"),
+                &Cache::at(&temp_dir.path().join("cache"), false),
+            )
+            .unwrap();
+
+        assert!(path.join("gradle.properties").exists());
+    }
+
+    #[test]
+    fn test_versions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        for branch in ["1.20", "1.9", "main"] {
+            create_text_file(&template_path.join("gradle.properties"), branch);
+            commit_all(&template_path, branch);
+        }
+
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+        let versions = Template::parse(template_path.to_str().unwrap())
+            .unwrap()
+            .versions(&cache)
+            .unwrap();
+        assert_eq!(
+            versions,
+            vec!["1.9".parse().unwrap(), "1.20".parse().unwrap()]
+        );
+
+        let builtin = Template::Builtin(Language::JAVA).versions(&cache).unwrap();
+        assert_eq!(builtin, vec![builtin::MINECRAFT_VERSION.parse().unwrap()]);
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use crate::code::language::Language;
+
+/// The Minecraft version (template branch) that the built-in templates target
+pub const MINECRAFT_VERSION: &str = "1.19";
+
+pub type Files = &'static [(&'static str, &'static [u8])];
+
+macro_rules! template_file {
+    ($language:literal, $path:literal) => {
+        (
+            $path,
+            include_bytes!(concat!("../../templates/", $language, "/", $path)) as &[u8],
+        )
+    };
+}
+
+pub const JAVA: Files = &[
+    template_file!("java", ".gitignore"),
+    template_file!("java", "build.gradle"),
+    template_file!("java", "gradle/wrapper/gradle-wrapper.properties"),
+    template_file!("java", "gradle.properties"),
+    template_file!("java", "gradlew"),
+    template_file!("java", "gradlew.bat"),
+    template_file!("java", "settings.gradle"),
+    template_file!("java", "src/main/java/net/fabricmc/example/ExampleMod.java"),
+    template_file!(
+        "java",
+        "src/main/java/net/fabricmc/example/mixin/ExampleMixin.java"
+    ),
+    template_file!("java", "src/main/resources/assets/modid/icon.png"),
+    template_file!("java", "src/main/resources/fabric.mod.json"),
+    template_file!("java", "src/main/resources/modid.mixins.json"),
+];
+
+pub const KOTLIN: Files = &[
+    template_file!("kotlin", ".gitignore"),
+    template_file!("kotlin", "build.gradle"),
+    template_file!("kotlin", "gradle/wrapper/gradle-wrapper.properties"),
+    template_file!("kotlin", "gradle.properties"),
+    template_file!("kotlin", "gradlew"),
+    template_file!("kotlin", "gradlew.bat"),
+    template_file!("kotlin", "settings.gradle"),
+    template_file!(
+        "kotlin",
+        "src/main/java/net/fabricmc/example/mixin/ExampleMixin.java"
+    ),
+    template_file!(
+        "kotlin",
+        "src/main/kotlin/net/fabricmc/example/ExampleMod.kt"
+    ),
+    template_file!("kotlin", "src/main/resources/assets/modid/icon.png"),
+    template_file!("kotlin", "src/main/resources/fabric.mod.json"),
+    template_file!("kotlin", "src/main/resources/modid.mixins.json"),
+];
+
+pub const SCALA: Files = &[
+    template_file!("scala", ".gitignore"),
+    template_file!("scala", "build.gradle"),
+    template_file!("scala", "gradle/wrapper/gradle-wrapper.properties"),
+    template_file!("scala", "gradle.properties"),
+    template_file!("scala", "gradlew"),
+    template_file!("scala", "gradlew.bat"),
+    template_file!("scala", "settings.gradle"),
+    template_file!(
+        "scala",
+        "src/main/java/net/fabricmc/example/mixin/ExampleMixin.java"
+    ),
+    template_file!(
+        "scala",
+        "src/main/scala/net/fabricmc/example/ExampleMod.scala"
+    ),
+    template_file!("scala", "src/main/resources/assets/modid/icon.png"),
+    template_file!("scala", "src/main/resources/fabric.mod.json"),
+    template_file!("scala", "src/main/resources/modid.mixins.json"),
+];
+
+/// Writes the built-in template for `language` into `path`
+pub fn write(path: &Path, language: &Language) -> std::io::Result<()> {
+    for (name, contents) in language.backend().builtin {
+        let file_path = path.join(name);
+        std::fs::create_dir_all(file_path.parent().unwrap())?;
+        std::fs::write(&file_path, contents)?;
+        #[cfg(unix)]
+        if *name == "gradlew" {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o755))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(Language::JAVA)]
+    #[case(Language::KOTLIN)]
+    #[case(Language::SCALA)]
+    fn test_write(#[case] language: Language) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write(temp_dir.path(), &language).unwrap();
+
+        let entrypoint = temp_dir
+            .path()
+            .join("src/main")
+            .join(language.source_dir())
+            .join("net/fabricmc/example/ExampleMod.".to_string() + language.extension());
+        assert!(entrypoint.exists());
+
+        let mod_json = temp_dir.path().join("src/main/resources/fabric.mod.json");
+        let contents = std::fs::read_to_string(mod_json).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&contents).is_ok());
+
+        // The wrapper scripts and properties are included, but not the
+        // wrapper jar, which `gradle wrapper` adds
+        let gradlew = temp_dir.path().join("gradlew");
+        assert!(gradlew.exists());
+        assert!(temp_dir.path().join("gradlew.bat").exists());
+        let wrapper = temp_dir.path().join("gradle/wrapper");
+        assert!(wrapper.join("gradle-wrapper.properties").exists());
+        assert!(!wrapper.join("gradle-wrapper.jar").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(gradlew).unwrap().permissions().mode();
+            assert_ne!(mode & 0o111, 0);
+        }
+    }
+}
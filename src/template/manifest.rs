@@ -0,0 +1,199 @@
+use std::path::Path;
+
+/// Name of the manifest file in the template root
+pub const FILE_NAME: &str = "make-fabric-mod.toml";
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(error: toml::de::Error) -> Self {
+        Error {
+            message: format!("Invalid {}: {}", FILE_NAME, error),
+        }
+    }
+}
+
+/// A file or directory to move, relative to the template root. `{mod_id}` in
+/// `to` is replaced with the new mod ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Describes the placeholders used by a template. Templates without a
+/// manifest (like the official example mods) use the defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    /// Package containing the main class (e.g., `net.fabricmc.example`)
+    pub package: String,
+    /// Simple name of the main class (e.g., `ExampleMod`)
+    pub main_class: String,
+    /// Mod ID used in string literals and mixin config names
+    pub mod_id: String,
+    pub rename: Vec<Rename>,
+    pub delete: Vec<String>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            package: "net.fabricmc.example".to_string(),
+            main_class: "ExampleMod".to_string(),
+            mod_id: "modid".to_string(),
+            rename: vec![Rename {
+                from: "src/main/resources/assets/modid".to_string(),
+                to: "src/main/resources/assets/{mod_id}".to_string(),
+            }],
+            delete: vec!["LICENSE".to_string()],
+        }
+    }
+}
+
+impl Manifest {
+    /// Reads the manifest in the template root, falling back to the defaults
+    /// for any missing field
+    pub fn load(template_root: &Path) -> Result<Self, Error> {
+        let path = template_root.join(FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, Error> {
+        let table = contents.parse::<toml::Table>()?;
+        let mut manifest = Self::default();
+
+        let string = |key: &str, default: &mut String| -> Result<(), Error> {
+            match table.get(key) {
+                Some(toml::Value::String(value)) => {
+                    *default = value.clone();
+                    Ok(())
+                }
+                Some(_) => Err(Error {
+                    message: format!("Invalid {}: `{}` must be a string", FILE_NAME, key),
+                }),
+                None => Ok(()),
+            }
+        };
+        string("package", &mut manifest.package)?;
+        string("main_class", &mut manifest.main_class)?;
+        string("mod_id", &mut manifest.mod_id)?;
+
+        if let Some(rename) = table.get("rename") {
+            manifest.rename = parse_renames(rename)?;
+        }
+        if let Some(delete) = table.get("delete") {
+            manifest.delete = parse_strings("delete", delete)?;
+        }
+
+        Ok(manifest)
+    }
+}
+
+fn parse_strings(key: &str, value: &toml::Value) -> Result<Vec<String>, Error> {
+    let error = || Error {
+        message: format!("Invalid {}: `{}` must be a list of strings", FILE_NAME, key),
+    };
+    value
+        .as_array()
+        .ok_or_else(error)?
+        .iter()
+        .map(|item| item.as_str().map(|s| s.to_string()).ok_or_else(error))
+        .collect()
+}
+
+fn parse_renames(value: &toml::Value) -> Result<Vec<Rename>, Error> {
+    let error = || Error {
+        message: format!(
+            "Invalid {}: `rename` entries must have `from` and `to` strings",
+            FILE_NAME
+        ),
+    };
+    value
+        .as_array()
+        .ok_or_else(error)?
+        .iter()
+        .map(|item| {
+            let from = item
+                .get("from")
+                .and_then(|v| v.as_str())
+                .ok_or_else(error)?;
+            let to = item.get("to").and_then(|v| v.as_str()).ok_or_else(error)?;
+            Ok(Rename {
+                from: from.to_string(),
+                to: to.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_without_manifest() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            Manifest::load(temp_dir.path()).unwrap(),
+            Manifest::default()
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let manifest = Manifest::parse(
+            r#"
+package = "com.acme.template"
+mod_id = "template"
+delete = ["LICENSE", "README.md"]
+
+[[rename]]
+from = "src/main/resources/assets/template"
+to = "src/main/resources/assets/{mod_id}"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.package, "com.acme.template");
+        assert_eq!(manifest.mod_id, "template");
+        assert_eq!(manifest.delete, vec!["LICENSE", "README.md"]);
+        assert_eq!(
+            manifest.rename,
+            vec![Rename {
+                from: "src/main/resources/assets/template".to_string(),
+                to: "src/main/resources/assets/{mod_id}".to_string(),
+            }]
+        );
+
+        // Missing fields keep their defaults
+        assert_eq!(manifest.main_class, "ExampleMod");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Manifest::parse("package = 1").is_err());
+        assert!(Manifest::parse("delete = \"LICENSE\"").is_err());
+        assert!(Manifest::parse("[[rename]]\nfrom = \"a\"").is_err());
+    }
+}
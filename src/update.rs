@@ -0,0 +1,396 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    cache::Cache,
+    fabric::{self, Options},
+    file, git,
+    lockfile::{self, Lockfile},
+};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<fabric::Error> for Error {
+    fn from(error: fabric::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<lockfile::Error> for Error {
+    fn from(error: lockfile::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<file::Error> for Error {
+    fn from(error: file::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<git::Error> for Error {
+    fn from(error: git::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// What happened to a single file during an update
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Added,
+    Updated,
+    Removed,
+    Merged,
+    /// Conflict markers were written into the file
+    Conflict,
+    /// The template and the mod both changed a file that can't be merged
+    /// (e.g., a binary file, or one deleted on only one side). The mod's
+    /// version was kept.
+    Skipped,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Change::Added => "added",
+            Change::Updated => "updated",
+            Change::Removed => "removed",
+            Change::Merged => "merged",
+            Change::Conflict => "conflict",
+            Change::Skipped => "skipped",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn read(path: &Path) -> Result<Option<Vec<u8>>, Error> {
+    if path.is_file() {
+        Ok(Some(std::fs::read(path)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Whether a file looks binary to git, i.e., has a NUL byte in its first 8000
+/// bytes
+fn is_binary(contents: &[u8]) -> bool {
+    contents.iter().take(8000).any(|&byte| byte == 0)
+}
+
+/// Copies a file from a generated project, keeping its permissions (e.g., of
+/// gradlew)
+fn copy(from: &Path, to: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(to.parent().unwrap())?;
+    std::fs::copy(from, to)?;
+    Ok(())
+}
+
+/// A mod's template generated at the revision the mod was created from
+/// (`base`) and with new options (`other`)
+pub struct Regenerated {
+    // Removes the generated projects when dropped
+    _temp_dir: tempfile::TempDir,
+    pub base: PathBuf,
+    pub other: PathBuf,
+    /// Lockfile of the `other` project
+    pub lockfile: Lockfile,
+}
+
+impl Regenerated {
+    /// Generates the template of a mod side by side at the revision in its
+    /// lockfile and with `options`
+    pub fn new(lockfile: &Lockfile, options: &Options, cache: &Cache) -> Result<Self, Error> {
+        let template = &lockfile.options.template;
+        let commit = match (template.cache_url(), &lockfile.commit) {
+            (Some(_), Some(commit)) => commit,
+            _ => {
+                return Err(Error {
+                    message: format!(
+                        "Only mods generated from a git template are supported (template: {})",
+                        template
+                    ),
+                })
+            }
+        };
+
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path().join("base");
+        let other = temp_dir.path().join("other");
+        let base_options = Options {
+            template_ref: Some(commit.clone()),
+            ..lockfile.options.clone()
+        };
+        fabric::create_mod(&base, &base_options, cache)?;
+        fabric::create_mod(&other, options, cache)?;
+
+        let updated = Lockfile::read(&other)?;
+        if updated.options.template != *template {
+            return Err(Error {
+                message: format!("Could not fetch template {}", template),
+            });
+        }
+
+        Ok(Self {
+            _temp_dir: temp_dir,
+            base,
+            other,
+            lockfile: updated,
+        })
+    }
+
+    /// Lists the files that differ between the two projects, except for the
+    /// lockfile
+    pub fn changed_files(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut files = file::list_files(&self.base)?;
+        files.extend(file::list_files(&self.other)?);
+        files.sort();
+        files.dedup();
+
+        let mut changed = Vec::new();
+        for file in files {
+            if file != Path::new(lockfile::FILE_NAME)
+                && read(&self.base.join(&file))? != read(&self.other.join(&file))?
+            {
+                changed.push(file);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Applies the change to `file` between the two projects to the mod at
+    /// `path`
+    pub fn merge(
+        &self,
+        path: &Path,
+        file: &Path,
+        repo: &git::Context,
+    ) -> Result<Option<Change>, Error> {
+        let path = path.join(file);
+        let base_path = self.base.join(file);
+        let other_path = self.other.join(file);
+        let current = read(&path)?;
+        let base = read(&base_path)?;
+        let other = read(&other_path)?;
+        if base == other || current == other {
+            return Ok(None);
+        }
+
+        let change = match (current, base, other) {
+            // Untouched by the mod, so take the template's version
+            (None, None, Some(_)) => {
+                copy(&other_path, &path)?;
+                Change::Added
+            }
+            (Some(current), Some(base), None) if current == base => {
+                std::fs::remove_file(&path)?;
+                file::remove_empty_parent_dirs(&path)?;
+                Change::Removed
+            }
+            (Some(current), Some(base), Some(_)) if current == base => {
+                copy(&other_path, &path)?;
+                Change::Updated
+            }
+
+            // Changed on both sides, but binary files can't be merged
+            (Some(current), base, Some(other))
+                if is_binary(&current)
+                    || is_binary(&other)
+                    || base.as_deref().is_some_and(is_binary) =>
+            {
+                Change::Skipped
+            }
+            (Some(_), base, Some(_)) => {
+                // A file added on both sides is merged against an empty base
+                let empty_path;
+                let base_path = if base.is_some() {
+                    &base_path
+                } else {
+                    empty_path = other_path.with_extension("empty");
+                    std::fs::write(&empty_path, "")?;
+                    &empty_path
+                };
+                let labels = ["mod", "original template", "updated template"];
+                if repo.merge_file(&path, base_path, &other_path, labels)? {
+                    Change::Conflict
+                } else {
+                    Change::Merged
+                }
+            }
+            _ => Change::Skipped,
+        };
+        Ok(Some(change))
+    }
+}
+
+/// Regenerates the template of the mod at `path` at the revision it was
+/// created from and at `template_ref` (or the ref it was created with), then
+/// merges the differences into the mod. Returns the changed files.
+pub fn update_mod(
+    path: &Path,
+    template_ref: Option<&str>,
+    cache: &Cache,
+) -> Result<Vec<(PathBuf, Change)>, Error> {
+    let lockfile = Lockfile::read(path)?;
+    let options = Options {
+        template_ref: template_ref
+            .map(|r| r.to_string())
+            .or_else(|| lockfile.options.template_ref.clone()),
+        ..lockfile.options.clone()
+    };
+    let regenerated = Regenerated::new(&lockfile, &options, cache)?;
+
+    let repo = git::Context::new(&Some(path))?;
+    let mut changes = Vec::new();
+    for file in regenerated.changed_files()? {
+        if let Some(change) = regenerated.merge(path, &file, &repo)? {
+            changes.push((file, change));
+        }
+    }
+
+    // Record the new revision
+    regenerated.lockfile.write(path)?;
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        code::language::Language,
+        fabric::tests::{create_template, example_options},
+        template::tests::commit_all,
+        template::Template,
+    };
+
+    #[test]
+    fn test_update_mod() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_template(&template_path, &Language::JAVA);
+        fs::write(template_path.join("build.gradle"), "a\nb\nc\nd\ne\n").unwrap();
+        fs::write(template_path.join("settings.gradle"), "x\n").unwrap();
+        fs::write(template_path.join("README.md"), "readme\n").unwrap();
+        commit_all(&template_path, "1.19");
+
+        let path = temp_dir.path().join("example-mod");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+        let options = Options {
+            template: Template::parse(template_path.to_str().unwrap()).unwrap(),
+            ..example_options(Language::JAVA)
+        };
+        fabric::create_mod(&path, &options, &cache).unwrap();
+
+        // Nothing to do until the template changes
+        assert!(update_mod(&path, None, &cache).unwrap().is_empty());
+
+        // Change the mod and the template
+        fs::write(path.join("build.gradle"), "a\nb\nc\nd\ne\nmod\n").unwrap();
+        fs::write(path.join("settings.gradle"), "mod\n").unwrap();
+        fs::write(template_path.join("build.gradle"), "template\nb\nc\nd\ne\n").unwrap();
+        fs::write(template_path.join("settings.gradle"), "template\n").unwrap();
+        fs::write(template_path.join("gradle.properties"), "a=b\n").unwrap();
+        fs::write(template_path.join("gradlew"), "#!/bin/sh\n").unwrap();
+        fs::remove_file(template_path.join("README.md")).unwrap();
+        commit_all(&template_path, "1.19");
+
+        let changes = update_mod(&path, None, &cache).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                (PathBuf::from("README.md"), Change::Removed),
+                (PathBuf::from("build.gradle"), Change::Merged),
+                (PathBuf::from("gradle.properties"), Change::Updated),
+                (PathBuf::from("gradlew"), Change::Added),
+                (PathBuf::from("settings.gradle"), Change::Conflict),
+            ]
+        );
+
+        let build = fs::read_to_string(path.join("build.gradle")).unwrap();
+        assert_eq!(build, "template\nb\nc\nd\ne\nmod\n");
+        let settings = fs::read_to_string(path.join("settings.gradle")).unwrap();
+        assert!(settings.contains("<<<<<<< mod\nmod\n"));
+        assert!(settings.contains("template\n>>>>>>> updated template"));
+
+        // The lockfile points at the new revision
+        let lockfile = Lockfile::read(&path).unwrap();
+        let head = git::Context::new(&Some(&template_path))
+            .unwrap()
+            .git(&["rev-parse", "HEAD"])
+            .unwrap();
+        assert_eq!(lockfile.commit.as_deref(), Some(head.trim()));
+        assert_eq!(lockfile.options.mod_id, options.mod_id);
+        assert_eq!(lockfile.options.template_ref, None);
+    }
+
+    #[test]
+    fn test_update_mod_binary_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template_path = temp_dir.path().join("template");
+        create_template(&template_path, &Language::JAVA);
+        let icon = "src/main/resources/assets/modid/icon.png";
+        fs::write(template_path.join(icon), b"\x89PNG\0a").unwrap();
+        commit_all(&template_path, "1.19");
+
+        let path = temp_dir.path().join("example-mod");
+        let cache = Cache::at(&temp_dir.path().join("cache"), false);
+        let options = Options {
+            template: Template::parse(template_path.to_str().unwrap()).unwrap(),
+            ..example_options(Language::JAVA)
+        };
+        fabric::create_mod(&path, &options, &cache).unwrap();
+
+        // Binary files changed on both sides are left as they are in the mod
+        let mod_icon = path.join("src/main/resources/assets/example-mod/icon.png");
+        fs::write(&mod_icon, b"\x89PNG\0mod").unwrap();
+        fs::write(template_path.join(icon), b"\x89PNG\0template").unwrap();
+        commit_all(&template_path, "1.19");
+
+        let changes = update_mod(&path, None, &cache).unwrap();
+        assert_eq!(
+            changes,
+            vec![(
+                PathBuf::from("src/main/resources/assets/example-mod/icon.png"),
+                Change::Skipped
+            )]
+        );
+        assert_eq!(fs::read(mod_icon).unwrap(), b"\x89PNG\0mod");
+    }
+
+    #[test]
+    fn test_update_mod_builtin_template() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("example-mod");
+        let cache = Cache::at(&temp_dir.path().join("cache"), true);
+        let options = example_options(Language::JAVA);
+        fabric::create_mod(&path, &options, &cache).unwrap();
+
+        assert!(update_mod(&path, None, &cache).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+package net.fabricmc.example
+
+import net.fabricmc.api.ClientModInitializer
+
+class ExampleModClient : ClientModInitializer {
+    override fun onInitializeClient() {
+        // This entrypoint is suitable for setting up client-specific logic, such as rendering.
+    }
+}
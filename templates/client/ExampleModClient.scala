@@ -0,0 +1,9 @@
+package net.fabricmc.example
+
+import net.fabricmc.api.ClientModInitializer
+
+object ExampleModClient extends ClientModInitializer {
+  override def onInitializeClient(): Unit = {
+    // This entrypoint is suitable for setting up client-specific logic, such as rendering.
+  }
+}
@@ -0,0 +1,11 @@
+package net.fabricmc.example;
+
+import net.fabricmc.fabric.api.datagen.v1.DataGeneratorEntrypoint;
+import net.fabricmc.fabric.api.datagen.v1.FabricDataGenerator;
+
+public class ExampleModDataGenerator implements DataGeneratorEntrypoint {
+	@Override
+	public void onInitializeDataGenerator(FabricDataGenerator fabricDataGenerator) {
+		// Add providers here. Run `gradlew runDatagen` to generate resources into src/main/generated.
+	}
+}
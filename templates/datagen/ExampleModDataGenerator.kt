@@ -0,0 +1,10 @@
+package net.fabricmc.example
+
+import net.fabricmc.fabric.api.datagen.v1.DataGeneratorEntrypoint
+import net.fabricmc.fabric.api.datagen.v1.FabricDataGenerator
+
+class ExampleModDataGenerator : DataGeneratorEntrypoint {
+    override fun onInitializeDataGenerator(fabricDataGenerator: FabricDataGenerator) {
+        // Add providers here. Run `gradlew runDatagen` to generate resources into src/main/generated.
+    }
+}
@@ -0,0 +1,9 @@
+package net.fabricmc.example
+
+import net.fabricmc.fabric.api.datagen.v1.{DataGeneratorEntrypoint, FabricDataGenerator}
+
+object ExampleModDataGenerator extends DataGeneratorEntrypoint {
+  override def onInitializeDataGenerator(fabricDataGenerator: FabricDataGenerator): Unit = {
+    // Add providers here. Run `gradlew runDatagen` to generate resources into src/main/generated.
+  }
+}